@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use day2::compile::CompiledMachine;
+use day2::loader::parse_text;
+use day2::{Machine, State};
+
+// sum 1..=n in a loop, which spends nearly all its time in four instructions
+#[rustfmt::skip]
+const SUM: [i64; 16] = [
+    3, 100,
+    1, 101, 100, 101,
+    1001, 100, -1, 100,
+    1005, 100, 2,
+    4, 101,
+    99,
+];
+
+fn time<F: FnMut() -> i64>(name: &str, runs: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    let mut result = 0;
+    for _ in 0..runs {
+        result = f();
+    }
+    let elapsed = start.elapsed() / runs;
+    println!("{:<24} {:>12?} per run (result {})", name, elapsed, result);
+    elapsed
+}
+
+fn sum_interpreted(n: i64) -> i64 {
+    let mut machine = Machine::new(SUM.to_vec());
+    machine.push_input(n);
+    match machine.run() {
+        Ok(State::Output(value)) => value,
+        state => panic!("Unexpected state: {:?}", state),
+    }
+}
+
+fn sum_compiled(n: i64) -> i64 {
+    let mut machine = CompiledMachine::new(SUM.to_vec());
+    machine.push_input(n);
+    match machine.run() {
+        Ok(State::Output(value)) => value,
+        state => panic!("Unexpected state: {:?}", state),
+    }
+}
+
+// the day2 noun/verb search: many short runs, each paying for translation
+fn search(run: fn(Vec<i64>) -> Vec<i64>) -> i64 {
+    let program = parse_text(include_str!("../input.txt")).unwrap();
+    for noun in 0..100 {
+        for verb in 0..100 {
+            let mut program = program.clone();
+            program[1] = noun;
+            program[2] = verb;
+            if run(program)[0] == 19690720 {
+                return 100 * noun + verb;
+            }
+        }
+    }
+    -1
+}
+
+fn main() {
+    let n = 1_000_000;
+    let interpreted = time("sum interpreted", 5, || sum_interpreted(n));
+    let compiled = time("sum compiled", 5, || sum_compiled(n));
+    println!(
+        "speedup: {:.2}x",
+        interpreted.as_secs_f64() / compiled.as_secs_f64()
+    );
+
+    let interpreted = time("search interpreted", 5, || search(day2::interpret));
+    let compiled =
+        time("search compiled", 5, || search(day2::compile::execute));
+    println!(
+        "speedup: {:.2}x",
+        interpreted.as_secs_f64() / compiled.as_secs_f64()
+    );
+}
@@ -0,0 +1,321 @@
+use std::env;
+use std::process;
+
+use day2::disasm;
+use day2::loader;
+use day2::{interpret_with, search, Machine, OpCode, State, Stop, MAX_MEMORY};
+
+const EXIT_HALTED: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_LIMIT: i32 = 2;
+const EXIT_NEED_INPUT: i32 = 3;
+const EXIT_USAGE: i32 = 64;
+
+const USAGE: &str = "\
+usage: intcode <command> <program> [options]
+
+programs may be text, varint encoded or gzip compressed
+
+commands:
+  run        run the program
+  disasm     print the program's instructions
+  search     find the noun and verb that leave --target at address 0
+  trace      run the program, printing each instruction as it executes
+
+options:
+  --patch ADDR=VALUE   set a memory cell before running (repeatable)
+  --input VALUES       comma separated values to feed the program (repeatable)
+  --limit STEPS        stop after this many instructions
+  --target VALUE       value searched for by `search` (default 19690720)
+  --dump-memory        print memory once the program stops
+  --print-output       print each value the program outputs
+
+exit status: 0 halted, 1 error, 2 step limit exceeded, 3 ran out of input,
+64 bad usage";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Run,
+    Disasm,
+    Search,
+    Trace,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    program: String,
+    patches: Vec<(usize, i64)>,
+    input: Vec<i64>,
+    limit: Option<u64>,
+    target: i64,
+    dump_memory: bool,
+    print_output: bool,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+
+    let program = match load(&options) {
+        Ok(program) => program,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(EXIT_ERROR);
+        }
+    };
+
+    process::exit(match options.command {
+        Command::Run => run(program, &options),
+        Command::Disasm => disassemble(&program),
+        Command::Search => find(&program, &options),
+        Command::Trace => trace(program, &options),
+    });
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let command = match args.next().map(String::as_str) {
+        Some("run") => Command::Run,
+        Some("disasm") => Command::Disasm,
+        Some("search") => Command::Search,
+        Some("trace") => Command::Trace,
+        Some(command) => return Err(format!("Unknown command: {}", command)),
+        None => return Err("No command given".to_string()),
+    };
+
+    let mut options = Options {
+        command,
+        program: String::new(),
+        patches: Vec::new(),
+        input: Vec::new(),
+        limit: None,
+        target: 19690720,
+        dump_memory: false,
+        print_output: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--patch" => {
+                let patch = value()?;
+                let mut parts = patch.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(address), Some(v)) => {
+                        let address: usize = parse(address, arg)?;
+                        if address >= MAX_MEMORY {
+                            return Err(format!(
+                                "Patch address {} is past the end of memory",
+                                address
+                            ));
+                        }
+                        options.patches.push((address, parse(v, arg)?))
+                    }
+                    _ => return Err(format!("Bad patch: {}", patch)),
+                }
+            }
+            "--input" => {
+                for v in value()?.split(',') {
+                    options.input.push(parse(v, arg)?);
+                }
+            }
+            "--limit" => options.limit = Some(parse(value()?, arg)?),
+            "--target" => options.target = parse(value()?, arg)?,
+            "--dump-memory" => options.dump_memory = true,
+            "--print-output" => options.print_output = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option: {}", flag))
+            }
+            path if options.program.is_empty() => {
+                options.program = path.to_string()
+            }
+            extra => return Err(format!("Unexpected argument: {}", extra)),
+        }
+    }
+
+    if options.program.is_empty() {
+        return Err("No program given".to_string());
+    }
+    Ok(options)
+}
+
+fn parse<T: std::str::FromStr>(s: &str, option: &str) -> Result<T, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("Bad value for {}: {}", option, s))
+}
+
+fn load(options: &Options) -> Result<Vec<i64>, String> {
+    let mut program = loader::load(&options.program)
+        .map_err(|e| format!("{}: {}", options.program, e))?;
+
+    for &(address, value) in &options.patches {
+        if address >= program.len() {
+            program.resize(address + 1, 0);
+        }
+        program[address] = value;
+    }
+    Ok(program)
+}
+
+fn run(program: Vec<i64>, options: &Options) -> i32 {
+    let outcome = match interpret_with(program, &options.input, options.limit) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return EXIT_ERROR;
+        }
+    };
+
+    if options.print_output {
+        for value in &outcome.output {
+            println!("{}", value);
+        }
+    }
+    if options.dump_memory {
+        let cells: Vec<String> =
+            outcome.memory.iter().map(i64::to_string).collect();
+        println!("{}", cells.join(","));
+    }
+
+    match outcome.stop {
+        Stop::Halted => EXIT_HALTED,
+        Stop::NeedInput => {
+            eprintln!("Program ran out of input after {} steps", outcome.steps);
+            EXIT_NEED_INPUT
+        }
+        Stop::LimitExceeded => {
+            eprintln!("Step limit of {} exceeded", outcome.steps);
+            EXIT_LIMIT
+        }
+    }
+}
+
+fn disassemble(program: &[i64]) -> i32 {
+    for (pc, line) in disasm::disassemble(program) {
+        println!("{:>6}  {}", pc, line);
+    }
+    EXIT_HALTED
+}
+
+fn find(program: &[i64], options: &Options) -> i32 {
+    let limit = options.limit.unwrap_or(1_000_000);
+    match search(program, options.target, limit) {
+        Some((noun, verb)) => {
+            println!(
+                "noun: {}, verb: {}, answer: {}",
+                noun,
+                verb,
+                100 * noun + verb
+            );
+            EXIT_HALTED
+        }
+        None => {
+            eprintln!("No noun and verb produce {}", options.target);
+            EXIT_ERROR
+        }
+    }
+}
+
+fn trace(program: Vec<i64>, options: &Options) -> i32 {
+    let mut machine = Machine::new(program);
+    for &value in &options.input {
+        machine.push_input(value);
+    }
+
+    let mut steps = 0;
+    loop {
+        if options.limit.is_some_and(|limit| steps >= limit) {
+            eprintln!("Step limit of {} exceeded", steps);
+            return EXIT_LIMIT;
+        }
+        if let Ok(op) = OpCode::new(machine.pc(), machine.memory()) {
+            println!("{:>6}  {}", machine.pc(), disasm::format(&op));
+        }
+        match machine.step() {
+            Ok(None) => {}
+            Ok(Some(State::Output(value))) => println!("        -> {}", value),
+            Ok(Some(State::NeedInput)) => {
+                eprintln!("Program ran out of input after {} steps", steps);
+                return EXIT_NEED_INPUT;
+            }
+            Ok(Some(State::Halted)) => return EXIT_HALTED,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return EXIT_ERROR;
+            }
+        }
+        steps += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn try_parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_run_options() {
+        let args = [
+            "run",
+            "prog.txt",
+            "--patch",
+            "1=12",
+            "--patch",
+            "2=2",
+            "--input",
+            "5",
+            "--input",
+            "6,-7",
+            "--dump-memory",
+            "--print-output",
+        ];
+        let options = try_parse(&args).unwrap();
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(options.program, "prog.txt");
+        assert_eq!(options.patches, vec![(1, 12), (2, 2)]);
+        assert_eq!(options.input, vec![5, 6, -7]);
+        assert!(options.dump_memory && options.print_output);
+    }
+
+    #[test]
+    fn test_bad_usage() {
+        assert!(try_parse(&[]).is_err());
+        assert!(try_parse(&["explode", "prog.txt"]).is_err());
+        assert!(try_parse(&["run"]).is_err());
+        assert!(try_parse(&["run", "a", "b"]).is_err());
+        assert!(try_parse(&["run", "a", "--patch", "1"]).is_err());
+        let huge = "99999999999999=1";
+        assert!(try_parse(&["run", "a", "--patch", huge]).is_err());
+        let past_end = format!("{}=1", MAX_MEMORY);
+        assert!(try_parse(&["run", "a", "--patch", &past_end]).is_err());
+        let last = format!("{}=1", MAX_MEMORY - 1);
+        assert!(try_parse(&["run", "a", "--patch", &last]).is_ok());
+        assert!(try_parse(&["run", "a", "--limit"]).is_err());
+        assert!(try_parse(&["run", "a", "--limit", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let options = try_parse(&["run", "-", "--limit", "3"]);
+        let options = options.unwrap();
+        assert_eq!(run(vec![1101, 1, 1, 5, 99], &options), EXIT_HALTED);
+        assert_eq!(run(vec![42], &options), EXIT_ERROR);
+        assert_eq!(run(vec![1105, 1, 0], &options), EXIT_LIMIT);
+        assert_eq!(run(vec![3, 0, 99], &options), EXIT_NEED_INPUT);
+    }
+}
@@ -0,0 +1,350 @@
+use crate::{Error, Machine, OpCode, State};
+
+// A parameter with its addressing mode already decoded.
+#[derive(Clone, Copy, Debug)]
+enum Param {
+    Position(usize),
+    Immediate(i64),
+    Relative(i64),
+}
+
+enum Effect {
+    Continue,
+    Wrote(usize),
+    Yield(State),
+}
+
+type Op = Box<dyn Fn(&mut Machine) -> Result<Effect, Error>>;
+
+// A machine whose reachable instructions have been translated ahead of time
+// into closures with their operands pre-bound, so running them skips the
+// decoding done by `OpCode::new` on every step.
+//
+// Instructions that can't be reached by following fall-through and immediate
+// jump targets from pc 0 are run by the interpreter instead. The same goes for
+// any translated instruction that the program later overwrites, so
+// self-modifying programs still behave exactly as they do when interpreted.
+pub struct CompiledMachine {
+    machine: Machine,
+    ops: Vec<Option<Op>>,
+    // the pc of the translated instruction covering each cell, if any, which
+    // may reach past the end of the program
+    owner: Vec<Option<usize>>,
+}
+
+impl CompiledMachine {
+    pub fn new(program: Vec<i64>) -> CompiledMachine {
+        let len = program.len();
+        let mut ops: Vec<Option<Op>> = (0..len).map(|_| None).collect();
+        let mut owner = vec![None; len];
+
+        let mut pending = vec![0];
+        while let Some(pc) = pending.pop() {
+            if pc >= len || owner[pc].is_some() {
+                continue;
+            }
+            let op = match OpCode::new(pc, &program) {
+                Ok(op) => op,
+                Err(_) => continue,
+            };
+            // operands can run past the end of the image, into memory the
+            // program may write to later
+            let cells = pc..pc + op.args().len();
+            if cells.clone().any(|c| matches!(owner.get(c), Some(Some(_)))) {
+                continue;
+            }
+            let next = pc + op.nargs();
+            match op {
+                OpCode::Halt => {}
+                OpCode::JumpIfTrue(ref args)
+                | OpCode::JumpIfFalse(ref args) => {
+                    if Machine::mode(args, 2) == 1 && args[2] >= 0 {
+                        pending.push(args[2] as usize);
+                    }
+                    pending.push(next);
+                }
+                _ => pending.push(next),
+            }
+            if let Some(compiled) = translate(&op, next) {
+                ops[pc] = Some(compiled);
+                if owner.len() < cells.end {
+                    owner.resize(cells.end, None);
+                }
+                for c in cells {
+                    owner[c] = Some(pc);
+                }
+            }
+        }
+
+        CompiledMachine {
+            machine: Machine::new(program),
+            ops,
+            owner,
+        }
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.machine.push_input(value);
+    }
+
+    pub fn memory(&self) -> &[i64] {
+        self.machine.memory()
+    }
+
+    pub fn into_memory(self) -> Vec<i64> {
+        self.machine.into_memory()
+    }
+
+    // Run until the program halts, produces a value or blocks on input.
+    pub fn run(&mut self) -> Result<State, Error> {
+        loop {
+            let pc = self.machine.pc;
+            let effect = match self.ops.get(pc) {
+                Some(Some(op)) => op(&mut self.machine)?,
+                _ => self.interpret_step()?,
+            };
+            match effect {
+                Effect::Continue => {}
+                Effect::Wrote(address) => self.invalidate(address),
+                Effect::Yield(state) => return Ok(state),
+            }
+        }
+    }
+
+    fn interpret_step(&mut self) -> Result<Effect, Error> {
+        let op = OpCode::new(self.machine.pc, &self.machine.memory)?;
+        let target = match op.params().1 {
+            Some(i) => self.machine.address(op.args(), i).ok(),
+            None => None,
+        };
+        match op.exec(&mut self.machine)? {
+            Some(state) => Ok(Effect::Yield(state)),
+            None => Ok(target.map_or(Effect::Continue, Effect::Wrote)),
+        }
+    }
+
+    // drop the translation of any instruction the program just overwrote
+    fn invalidate(&mut self, address: usize) {
+        if let Some(&Some(pc)) = self.owner.get(address) {
+            self.ops[pc] = None;
+        }
+    }
+}
+
+pub fn execute(program: Vec<i64>) -> Vec<i64> {
+    let mut machine = CompiledMachine::new(program);
+    loop {
+        match machine.run() {
+            Ok(State::Halted) => break,
+            Ok(State::Output(_)) => {}
+            Ok(State::NeedInput) => panic!("Program requested input"),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    machine.into_memory()
+}
+
+// Translate a single instruction, or return None to leave it to the
+// interpreter, e.g. when it would fail with an error.
+fn translate(op: &OpCode, next: usize) -> Option<Op> {
+    let args = op.args();
+    let param = |i: usize| -> Option<Param> {
+        match Machine::mode(args, i) {
+            0 if args[i] >= 0 => Some(Param::Position(args[i] as usize)),
+            1 => Some(Param::Immediate(args[i])),
+            2 => Some(Param::Relative(args[i])),
+            _ => None,
+        }
+    };
+    let (inputs, output) = op.params();
+    let operand = |n: usize| match inputs.get(n) {
+        Some(&i) => param(i),
+        None => Some(Param::Immediate(0)),
+    };
+    let (a, b) = (operand(0)?, operand(1)?);
+    let c = match output {
+        Some(i) => match param(i)? {
+            Param::Immediate(_) => return None,
+            c => c,
+        },
+        None => Param::Immediate(0),
+    };
+
+    let op: Op = match op {
+        OpCode::Add(_) => Box::new(move |m| {
+            let value = m.checked(load(m, a)?.checked_add(load(m, b)?))?;
+            let effect = store(m, c, value)?;
+            m.pc = next;
+            Ok(effect)
+        }),
+        OpCode::Mul(_) => Box::new(move |m| {
+            let value = m.checked(load(m, a)?.checked_mul(load(m, b)?))?;
+            let effect = store(m, c, value)?;
+            m.pc = next;
+            Ok(effect)
+        }),
+        OpCode::Input(_) => Box::new(move |m| match m.input.pop_front() {
+            Some(value) => {
+                let effect = store(m, c, value)?;
+                m.pc = next;
+                Ok(effect)
+            }
+            None => Ok(Effect::Yield(State::NeedInput)),
+        }),
+        OpCode::Output(_) => Box::new(move |m| {
+            let value = load(m, a)?;
+            m.pc = next;
+            Ok(Effect::Yield(State::Output(value)))
+        }),
+        OpCode::JumpIfTrue(_) => Box::new(move |m| {
+            m.pc = match load(m, a)? {
+                0 => next,
+                _ => m.jump_target(load(m, b)?)?,
+            };
+            Ok(Effect::Continue)
+        }),
+        OpCode::JumpIfFalse(_) => Box::new(move |m| {
+            m.pc = match load(m, a)? {
+                0 => m.jump_target(load(m, b)?)?,
+                _ => next,
+            };
+            Ok(Effect::Continue)
+        }),
+        OpCode::LessThan(_) => Box::new(move |m| {
+            let value = load(m, a)? < load(m, b)?;
+            let effect = store(m, c, value as i64)?;
+            m.pc = next;
+            Ok(effect)
+        }),
+        OpCode::Equals(_) => Box::new(move |m| {
+            let value = load(m, a)? == load(m, b)?;
+            let effect = store(m, c, value as i64)?;
+            m.pc = next;
+            Ok(effect)
+        }),
+        OpCode::AdjustBase(_) => Box::new(move |m| {
+            m.relative_base = m.relative(load(m, a)?)?;
+            m.pc = next;
+            Ok(Effect::Continue)
+        }),
+        OpCode::Halt => Box::new(|_| Ok(Effect::Yield(State::Halted))),
+    };
+    Some(op)
+}
+
+fn address(machine: &Machine, param: Param) -> Result<usize, Error> {
+    match param {
+        Param::Position(address) => Ok(address),
+        Param::Relative(offset) => {
+            machine.jump_target(machine.relative(offset)?)
+        }
+        Param::Immediate(_) => Err(Error::ImmediateWrite { pc: machine.pc }),
+    }
+}
+
+fn load(machine: &Machine, param: Param) -> Result<i64, Error> {
+    match param {
+        Param::Immediate(value) => Ok(value),
+        param => {
+            let address = address(machine, param)?;
+            Ok(machine.memory.get(address).copied().unwrap_or(0))
+        }
+    }
+}
+
+fn store(
+    machine: &mut Machine,
+    param: Param,
+    value: i64,
+) -> Result<Effect, Error> {
+    let address = address(machine, param)?;
+    machine.store(address, value)?;
+    Ok(Effect::Wrote(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{day2_examples, day2_input};
+    use crate::interpret;
+
+    fn outputs(program: Vec<i64>, input: &[i64]) -> (Vec<i64>, Vec<i64>) {
+        let mut machine = CompiledMachine::new(program);
+        for &value in input {
+            machine.push_input(value);
+        }
+        let mut output = Vec::new();
+        while let State::Output(value) = machine.run().unwrap() {
+            output.push(value);
+        }
+        (machine.into_memory(), output)
+    }
+
+    #[test]
+    fn test_day2_examples() {
+        for program in day2_examples() {
+            assert_eq!(execute(program.clone()), interpret(program));
+        }
+    }
+
+    #[test]
+    fn test_day2_input() {
+        let program = day2_input();
+        for &(noun, verb) in &[(12, 2), (76, 10)] {
+            let mut program = program.clone();
+            program[1] = noun;
+            program[2] = verb;
+            assert_eq!(execute(program.clone()), interpret(program));
+        }
+    }
+
+    #[test]
+    fn test_quine() {
+        let program = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        assert_eq!(outputs(program.clone(), &[]).1, program);
+    }
+
+    #[test]
+    fn test_loop() {
+        // sum 1..=n
+        #[rustfmt::skip]
+        let program = vec![
+            3, 100,
+            1, 101, 100, 101,
+            1001, 100, -1, 100,
+            1005, 100, 2,
+            4, 101,
+            99,
+        ];
+        assert_eq!(outputs(program, &[100]).1, vec![5050]);
+    }
+
+    #[test]
+    fn test_overwritten_code() {
+        // turn the halt at 4 into an output before reaching it
+        let program = vec![1101, 104, 0, 4, 99, 42, 99];
+        assert_eq!(outputs(program, &[]).1, vec![42]);
+    }
+
+    #[test]
+    fn test_overwritten_past_end() {
+        // the output at 8 reads a zero operand past the end of the image,
+        // which the add at 0 overwrites first
+        let program = vec![1101, 42, 0, 9, 1105, 1, 8, 0, 104];
+        let mut machine = CompiledMachine::new(program);
+        assert_eq!(machine.run(), Ok(State::Output(42)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut machine = CompiledMachine::new(vec![1, 0, 0, 0, 42]);
+        assert_eq!(
+            machine.run(),
+            Err(Error::UnknownOpCode { pc: 4, opcode: 42 })
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{Machine, OpCode};
+
+pub fn mnemonic(op: &OpCode) -> &'static str {
+    match op {
+        OpCode::Add(_) => "add",
+        OpCode::Mul(_) => "mul",
+        OpCode::Input(_) => "in",
+        OpCode::Output(_) => "out",
+        OpCode::JumpIfTrue(_) => "jnz",
+        OpCode::JumpIfFalse(_) => "jz",
+        OpCode::LessThan(_) => "lt",
+        OpCode::Equals(_) => "eq",
+        OpCode::AdjustBase(_) => "arb",
+        OpCode::Halt => "hlt",
+    }
+}
+
+// Position parameters are shown as [address], relative ones as [rb+offset]
+// and immediates as bare values.
+pub fn format(op: &OpCode) -> String {
+    let args = op.args();
+    let params: Vec<String> = (1..args.len())
+        .map(|i| match Machine::mode(args, i) {
+            0 => format!("[{}]", args[i]),
+            1 => format!("{}", args[i]),
+            2 if args[i] < 0 => format!("[rb{}]", args[i]),
+            2 => format!("[rb+{}]", args[i]),
+            mode => format!("?{}:{}", mode, args[i]),
+        })
+        .collect();
+
+    if params.is_empty() {
+        mnemonic(op).to_string()
+    } else {
+        format!("{} {}", mnemonic(op), params.join(", "))
+    }
+}
+
+// Decode the whole image front to back. Cells that don't start a valid
+// instruction are shown as data and skipped one at a time.
+pub fn disassemble(program: &[i64]) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pc = 0;
+    while pc < program.len() {
+        match OpCode::new(pc, program) {
+            Ok(op) if pc + op.args().len() <= program.len() => {
+                lines.push((pc, format(&op)));
+                pc += op.args().len();
+            }
+            _ => {
+                lines.push((pc, format!("data {}", program[pc])));
+                pc += 1;
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        let program = vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+        let lines: Vec<String> = disassemble(&program)
+            .into_iter()
+            .map(|(pc, line)| format!("{}: {}", pc, line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "0: add [9], [10], [3]",
+                "4: mul [3], [11], [0]",
+                "8: hlt",
+                "9: data 30",
+                "10: data 40",
+                "11: data 50",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modes() {
+        let op = OpCode::new(0, &[21107, -1, 5, 2]).unwrap();
+        assert_eq!(format(&op), "lt -1, 5, [rb+2]");
+        let op = OpCode::new(0, &[2006, 0, -3]).unwrap();
+        assert_eq!(format(&op), "jz [0], [rb-3]");
+    }
+}
@@ -0,0 +1,70 @@
+// Programs shared by the tests of several modules.
+
+use crate::loader::parse_text;
+
+// the day2 examples
+pub fn day2_examples() -> Vec<Vec<i64>> {
+    vec![
+        vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+        vec![1, 0, 0, 0, 99],
+        vec![2, 3, 0, 3, 99],
+        vec![2, 4, 4, 5, 99, 0],
+        vec![1, 1, 1, 4, 99, 5, 6, 0, 99],
+    ]
+}
+
+// the day2 puzzle input
+pub fn day2_input() -> Vec<i64> {
+    parse_text(include_str!("../input.txt")).unwrap()
+}
+
+// An amplifier program with the phase settings that give its largest signal.
+pub struct Amplifiers {
+    pub program: &'static [i64],
+    pub phases: [i64; 5],
+    pub signal: i64,
+}
+
+pub const CHAIN: Amplifiers = Amplifiers {
+    program: &[
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ],
+    phases: [4, 3, 2, 1, 0],
+    signal: 43210,
+};
+
+pub const FEEDBACK_LOOP: Amplifiers = Amplifiers {
+    program: &[
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27,
+        1001, 28, -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+    ],
+    phases: [9, 8, 7, 6, 5],
+    signal: 139629729,
+};
+
+// Make one channel per amplifier with `channel`, seeding each with its phase
+// setting and the first also with a zero. Returns each amplifier's input
+// along with a sender to the next one around the ring, which are then the
+// only senders left, so the ring shuts down once the amplifiers halt.
+pub fn ring<Tx: Clone, Rx>(
+    phases: &[i64],
+    mut channel: impl FnMut() -> (Tx, Rx),
+    send: impl Fn(&Tx, i64),
+) -> Vec<(Rx, Tx)> {
+    let channels: Vec<(Tx, Rx)> = phases
+        .iter()
+        .map(|&phase| {
+            let (tx, rx) = channel();
+            send(&tx, phase);
+            (tx, rx)
+        })
+        .collect();
+    send(&channels[0].0, 0);
+
+    let senders: Vec<Tx> = channels.iter().map(|(tx, _)| tx.clone()).collect();
+    channels
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, rx))| (rx, senders[(i + 1) % senders.len()].clone()))
+        .collect()
+}
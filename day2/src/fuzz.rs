@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use crate::{interpret_with, Error, Outcome, Stop, MAX_MEMORY};
+
+// Step limit for fuzzed programs, which are free to loop forever.
+const FUZZ_STEPS: u64 = 10_000;
+
+// Scratch cells placed after the code of generated programs.
+const DATA_CELLS: u64 = 16;
+
+// xorshift64*, which is plenty for generating test programs
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    // uniform in [low, high)
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + self.below((high - low) as u64) as i64
+    }
+}
+
+// Generate a program of `n` instructions followed by a halt and some data.
+//
+// Termination is guaranteed by construction: every jump is forward to the
+// start of a later instruction, and every write lands in the data area (or
+// past it, through the relative base, which only ever grows), so the code
+// can't be modified. Programs read at most `n` inputs. They may still stop
+// early with an overflow error if their arithmetic gets out of hand.
+pub fn random_program(rng: &mut Rng, n: usize) -> Vec<i64> {
+    let opcodes: Vec<i64> = (0..n).map(|_| rng.range(1, 10)).collect();
+    let widths: Vec<usize> = opcodes.iter().map(|&op| width(op)).collect();
+    let mut starts = Vec::with_capacity(n + 1);
+    let mut pc = 0;
+    for &w in &widths {
+        starts.push(pc);
+        pc += w;
+    }
+    starts.push(pc);
+    let data = pc as i64 + 1;
+
+    let mut program = Vec::new();
+    for (i, &opcode) in opcodes.iter().enumerate() {
+        let params: Vec<(i64, i64)> = match opcode {
+            1 | 2 | 7 | 8 => vec![
+                read_param(rng, data),
+                read_param(rng, data),
+                write_param(rng, data),
+            ],
+            3 => vec![write_param(rng, data)],
+            4 => vec![read_param(rng, data)],
+            5 | 6 => {
+                let later = rng.range(i as i64 + 1, n as i64 + 1);
+                vec![read_param(rng, data), (1, starts[later as usize] as i64)]
+            }
+            _ => vec![(1, rng.range(0, 4))],
+        };
+
+        let mut word = opcode;
+        for (p, &(mode, _)) in params.iter().enumerate() {
+            word += mode * 10_i64.pow(p as u32 + 2);
+        }
+        program.push(word);
+        program.extend(params.iter().map(|&(_, value)| value));
+    }
+
+    program.push(99);
+    program.extend((0..DATA_CELLS).map(|_| rng.range(-50, 50)));
+    program
+}
+
+fn width(opcode: i64) -> usize {
+    match opcode {
+        1 | 2 | 7 | 8 => 4,
+        5 | 6 => 3,
+        _ => 2,
+    }
+}
+
+// (mode, value) of a parameter that reads the data area or an immediate
+fn read_param(rng: &mut Rng, data: i64) -> (i64, i64) {
+    match rng.below(3) {
+        0 => (0, data + rng.below(DATA_CELLS) as i64),
+        1 => (1, rng.range(-50, 50)),
+        _ => (2, data + rng.below(DATA_CELLS) as i64),
+    }
+}
+
+fn write_param(rng: &mut Rng, data: i64) -> (i64, i64) {
+    let mode = if rng.below(2) == 0 { 0 } else { 2 };
+    (mode, data + rng.below(DATA_CELLS) as i64)
+}
+
+// A deliberately naive interpreter, written separately from `Machine`, for
+// checking it against. Memory is a map and every parameter is read straight
+// from it when needed.
+pub fn model(
+    program: &[i64],
+    input: &[i64],
+    max_steps: Option<u64>,
+) -> Result<Outcome, Error> {
+    let mut model = Model {
+        memory: program.iter().copied().enumerate().collect(),
+        len: program.len(),
+        pc: 0,
+        base: 0,
+    };
+    let mut input = input.iter().copied();
+    let mut output = Vec::new();
+    let mut steps = 0;
+
+    let stop = loop {
+        if max_steps.is_some_and(|max| steps >= max) {
+            break Stop::LimitExceeded;
+        }
+
+        let pc = model.pc;
+        let word = model.get(pc);
+        match word % 100 {
+            1 | 2 | 7 | 8 => {
+                let (a, b) = (model.value(1)?, model.value(2)?);
+                let result = match word % 100 {
+                    1 => a.checked_add(b).ok_or(Error::Overflow { pc })?,
+                    2 => a.checked_mul(b).ok_or(Error::Overflow { pc })?,
+                    7 => (a < b) as i64,
+                    _ => (a == b) as i64,
+                };
+                model.set(3, result)?;
+                model.pc += 4;
+            }
+            3 => match input.next() {
+                Some(value) => {
+                    model.set(1, value)?;
+                    model.pc += 2;
+                }
+                None => break Stop::NeedInput,
+            },
+            4 => {
+                output.push(model.value(1)?);
+                model.pc += 2;
+            }
+            5 | 6 => {
+                let condition = model.value(1)? != 0;
+                if condition == (word % 100 == 5) {
+                    let target = model.value(2)?;
+                    if target < 0 {
+                        return Err(Error::NegativeAddress {
+                            pc,
+                            address: target,
+                        });
+                    }
+                    model.pc = target as usize;
+                } else {
+                    model.pc += 3;
+                }
+            }
+            9 => {
+                let offset = model.value(1)?;
+                model.base = model
+                    .base
+                    .checked_add(offset)
+                    .ok_or(Error::Overflow { pc })?;
+                model.pc += 2;
+            }
+            99 => break Stop::Halted,
+            _ => return Err(Error::UnknownOpCode { pc, opcode: word }),
+        }
+        steps += 1;
+    };
+
+    let memory = (0..model.len).map(|a| model.get(a)).collect();
+    Ok(Outcome {
+        memory,
+        output,
+        steps,
+        stop,
+    })
+}
+
+struct Model {
+    memory: HashMap<usize, i64>,
+    len: usize,
+    pc: usize,
+    base: i64,
+}
+
+impl Model {
+    fn get(&self, address: usize) -> i64 {
+        self.memory.get(&address).copied().unwrap_or(0)
+    }
+
+    // the address parameter `n` refers to, or None if it's an immediate
+    fn address(&self, n: u32) -> Result<Option<usize>, Error> {
+        let pc = self.pc;
+        let raw = self.get(pc + n as usize);
+        let address = match self.get(pc) / 10_i64.pow(n + 1) % 10 {
+            0 => raw,
+            1 => return Ok(None),
+            2 => self.base.checked_add(raw).ok_or(Error::Overflow { pc })?,
+            mode => return Err(Error::UnknownMode { pc, mode }),
+        };
+        if address < 0 {
+            return Err(Error::NegativeAddress { pc, address });
+        }
+        Ok(Some(address as usize))
+    }
+
+    fn value(&self, n: u32) -> Result<i64, Error> {
+        match self.address(n)? {
+            Some(address) => Ok(self.get(address)),
+            None => Ok(self.get(self.pc + n as usize)),
+        }
+    }
+
+    fn set(&mut self, n: u32, value: i64) -> Result<(), Error> {
+        let pc = self.pc;
+        match self.address(n)? {
+            None => Err(Error::ImmediateWrite { pc }),
+            Some(address) if address >= MAX_MEMORY => {
+                Err(Error::AddressTooLarge { pc, address })
+            }
+            Some(address) => {
+                self.memory.insert(address, value);
+                self.len = self.len.max(address + 1);
+                Ok(())
+            }
+        }
+    }
+}
+
+// Turn arbitrary bytes into a program and its input. The first byte picks how
+// many input values follow, then each pair of bytes becomes one cell, with
+// the first byte of the pair choosing between an instruction (with random,
+// occasionally invalid, modes), a small value, an address or a huge value.
+// Instructions are the most likely so that programs get past their first
+// few steps.
+pub fn from_bytes(data: &[u8]) -> (Vec<i64>, Vec<i64>) {
+    let n_inputs = data.first().map_or(0, |&b| (b % 4) as usize);
+    let rest = data.get(1..).unwrap_or(&[]);
+    let (inputs, cells) = rest.split_at(n_inputs.min(rest.len()));
+
+    let input = inputs.iter().map(|&b| b as i8 as i64).collect();
+    let program = cells
+        .chunks(2)
+        .map(|pair| {
+            let (tag, value) = (pair[0] as i64, *pair.get(1).unwrap_or(&0));
+            match tag % 8 {
+                0..=3 => {
+                    let opcode =
+                        [1, 2, 3, 4, 5, 6, 7, 8, 9, 99][value as usize % 10];
+                    let modes = tag / 8 % 3 * 100 + tag / 24 % 3 * 1000;
+                    opcode + modes + tag / 72 % 4 * 10000
+                }
+                4 | 5 => value as i8 as i64,
+                6 => value as i64,
+                _ => i64::MAX - value as i64,
+            }
+        })
+        .collect();
+    (program, input)
+}
+
+// Entry point for coverage guided fuzzers, e.g. from a cargo-fuzz target:
+//
+//     fuzz_target!(|data: &[u8]| day2::fuzz::fuzz(data));
+//
+// Panics if the interpreter panics or disagrees with the reference model.
+pub fn fuzz(data: &[u8]) {
+    let (program, input) = from_bytes(data);
+    let actual = interpret_with(program.clone(), &input, Some(FUZZ_STEPS));
+    let expected = model(&program, &input, Some(FUZZ_STEPS));
+    assert_eq!(actual, expected, "program: {:?}", program);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::CompiledMachine;
+    use crate::State;
+
+    #[test]
+    fn test_generated_programs() {
+        let mut rng = Rng::new(2019);
+        for _ in 0..500 {
+            let n = rng.range(1, 40) as usize;
+            let program = random_program(&mut rng, n);
+            let input: Vec<i64> = (0..n).map(|_| rng.range(-9, 10)).collect();
+
+            let actual = interpret_with(program.clone(), &input, None);
+            assert_eq!(actual, model(&program, &input, None));
+            match actual {
+                Ok(outcome) => assert_eq!(outcome.stop, Stop::Halted),
+                Err(e) => assert!(matches!(e, Error::Overflow { .. })),
+            }
+        }
+    }
+
+    // run `program` compiled and interpreted and check they agree
+    fn compare_compiled(program: Vec<i64>, input: &[i64]) {
+        let expected = interpret_with(program.clone(), input, None);
+        let mut machine = CompiledMachine::new(program.clone());
+        for &value in input {
+            machine.push_input(value);
+        }
+        let mut output = Vec::new();
+        let result = loop {
+            match machine.run() {
+                Ok(State::Output(value)) => output.push(value),
+                Ok(_) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+
+        match expected {
+            Ok(outcome) => {
+                assert_eq!(result, Ok(()), "program: {:?}", program);
+                assert_eq!(output, outcome.output, "program: {:?}", program);
+                assert_eq!(machine.memory(), &outcome.memory[..]);
+            }
+            Err(e) => assert_eq!(result, Err(e), "program: {:?}", program),
+        }
+    }
+
+    #[test]
+    fn test_compiled_matches_interpreter() {
+        let mut rng = Rng::new(11);
+        for _ in 0..500 {
+            let n = rng.range(1, 40) as usize;
+            let program = random_program(&mut rng, n);
+            let input: Vec<i64> = (0..n).map(|_| rng.range(-9, 10)).collect();
+            compare_compiled(program, &input);
+        }
+
+        // generated programs never write into their own code
+        let self_modifying = vec![
+            vec![1101, 104, 0, 4, 99, 42, 99],
+            vec![1101, 42, 0, 9, 1105, 1, 8, 0, 104],
+            // as above, but writing a halt after the output so that the
+            // outputs are compared too
+            vec![1101, 42, 0, 13, 1101, 99, 0, 14, 1105, 1, 12, 0, 104],
+            vec![3, 7, 1105, 1, 6, 0, 1101, 0, 0, 0, 4, 0, 99],
+        ];
+        for program in self_modifying {
+            compare_compiled(program, &[2]);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_random_bytes() {
+        let mut rng = Rng::new(7);
+        for _ in 0..3000 {
+            let len = rng.below(64) as usize;
+            let data: Vec<u8> =
+                (0..len).map(|_| rng.below(256) as u8).collect();
+            fuzz(&data);
+        }
+    }
+
+    #[test]
+    fn test_structured_errors() {
+        let run = |program: Vec<i64>| interpret_with(program, &[], Some(100));
+        assert_eq!(
+            run(vec![1101, i64::MAX, 1, 0, 99]),
+            Err(Error::Overflow { pc: 0 })
+        );
+        assert_eq!(
+            run(vec![1101, 1, 1, 1 << 40, 99]),
+            Err(Error::AddressTooLarge {
+                pc: 0,
+                address: 1 << 40
+            })
+        );
+        assert_eq!(
+            run(vec![109, i64::MAX, 109, 1, 99]),
+            Err(Error::Overflow { pc: 2 })
+        );
+        assert_eq!(
+            run(vec![1105, 1, -4]),
+            Err(Error::NegativeAddress { pc: 0, address: -4 })
+        );
+        assert_eq!(
+            run(vec![1105, 1, i64::MAX]),
+            Err(Error::UnknownOpCode {
+                pc: i64::MAX as usize,
+                opcode: 0
+            })
+        );
+        assert_eq!(
+            run(vec![301, 0, 0, 0]),
+            Err(Error::UnknownMode { pc: 0, mode: 3 })
+        );
+    }
+}
@@ -0,0 +1,543 @@
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+
+pub mod compile;
+pub mod disasm;
+#[cfg(test)]
+mod fixtures;
+pub mod fuzz;
+pub mod loader;
+pub mod maze;
+pub mod optimize;
+pub mod robot;
+pub mod screen;
+pub mod stream;
+pub mod threaded;
+
+// Writes at or beyond this address fail rather than growing memory without
+// bound.
+pub const MAX_MEMORY: usize = 1 << 20;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    UnknownOpCode { pc: usize, opcode: i64 },
+    UnknownMode { pc: usize, mode: i64 },
+    ImmediateWrite { pc: usize },
+    NegativeAddress { pc: usize, address: i64 },
+    AddressTooLarge { pc: usize, address: usize },
+    Overflow { pc: usize },
+    // the machine wanted input but its source has been closed
+    InputClosed { pc: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownOpCode { pc, opcode } => {
+                write!(f, "Unknown opcode {} at pc {}", opcode, pc)
+            }
+            Error::UnknownMode { pc, mode } => {
+                write!(f, "Unknown parameter mode {} at pc {}", mode, pc)
+            }
+            Error::ImmediateWrite { pc } => {
+                write!(f, "Write to immediate parameter at pc {}", pc)
+            }
+            Error::NegativeAddress { pc, address } => {
+                write!(f, "Negative address {} at pc {}", address, pc)
+            }
+            Error::AddressTooLarge { pc, address } => {
+                write!(f, "Address {} too large at pc {}", address, pc)
+            }
+            Error::Overflow { pc } => {
+                write!(f, "Arithmetic overflow at pc {}", pc)
+            }
+            Error::InputClosed { pc } => {
+                write!(f, "Input closed while waiting at pc {}", pc)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+// Why a running machine handed control back to its caller.
+#[derive(Debug, PartialEq)]
+pub enum State {
+    Halted,
+    NeedInput,
+    Output(i64),
+}
+
+pub enum OpCode {
+    Add(Vec<i64>),
+    Mul(Vec<i64>),
+    Input(Vec<i64>),
+    Output(Vec<i64>),
+    JumpIfTrue(Vec<i64>),
+    JumpIfFalse(Vec<i64>),
+    LessThan(Vec<i64>),
+    Equals(Vec<i64>),
+    AdjustBase(Vec<i64>),
+    Halt,
+}
+
+impl OpCode {
+    pub fn new(pc: usize, program: &[i64]) -> Result<OpCode, Error> {
+        let opcode = program.get(pc).copied().unwrap_or(0);
+        let opcode = match opcode % 100 {
+            1 => OpCode::Add(OpCode::pack_args(pc, 4, program)),
+            2 => OpCode::Mul(OpCode::pack_args(pc, 4, program)),
+            3 => OpCode::Input(OpCode::pack_args(pc, 2, program)),
+            4 => OpCode::Output(OpCode::pack_args(pc, 2, program)),
+            5 => OpCode::JumpIfTrue(OpCode::pack_args(pc, 3, program)),
+            6 => OpCode::JumpIfFalse(OpCode::pack_args(pc, 3, program)),
+            7 => OpCode::LessThan(OpCode::pack_args(pc, 4, program)),
+            8 => OpCode::Equals(OpCode::pack_args(pc, 4, program)),
+            9 => OpCode::AdjustBase(OpCode::pack_args(pc, 2, program)),
+            99 => OpCode::Halt,
+            _ => return Err(Error::UnknownOpCode { pc, opcode }),
+        };
+        Ok(opcode)
+    }
+
+    fn nargs(&self) -> usize {
+        match self {
+            OpCode::Add(_) => 4,
+            OpCode::Mul(_) => 4,
+            OpCode::Input(_) => 2,
+            OpCode::Output(_) => 2,
+            OpCode::JumpIfTrue(_) => 3,
+            OpCode::JumpIfFalse(_) => 3,
+            OpCode::LessThan(_) => 4,
+            OpCode::Equals(_) => 4,
+            OpCode::AdjustBase(_) => 2,
+            OpCode::Halt => 0,
+        }
+    }
+
+    // the instruction's own cells, starting with the opcode itself
+    pub fn args(&self) -> &[i64] {
+        match self {
+            OpCode::Add(args)
+            | OpCode::Mul(args)
+            | OpCode::Input(args)
+            | OpCode::Output(args)
+            | OpCode::JumpIfTrue(args)
+            | OpCode::JumpIfFalse(args)
+            | OpCode::LessThan(args)
+            | OpCode::Equals(args)
+            | OpCode::AdjustBase(args) => args,
+            OpCode::Halt => &[99],
+        }
+    }
+
+    // indices of the parameters an instruction reads, and the one it writes
+    fn params(&self) -> (&'static [usize], Option<usize>) {
+        match self {
+            OpCode::Add(_)
+            | OpCode::Mul(_)
+            | OpCode::LessThan(_)
+            | OpCode::Equals(_) => (&[1, 2], Some(3)),
+            OpCode::Input(_) => (&[], Some(1)),
+            OpCode::Output(_) | OpCode::AdjustBase(_) => (&[1], None),
+            OpCode::JumpIfTrue(_) | OpCode::JumpIfFalse(_) => (&[1, 2], None),
+            OpCode::Halt => (&[], None),
+        }
+    }
+
+    // memory past the end of the program reads as zero
+    fn pack_args(pc: usize, capacity: usize, memory: &[i64]) -> Vec<i64> {
+        (pc..pc + capacity)
+            .map(|i| memory.get(i).copied().unwrap_or(0))
+            .collect()
+    }
+
+    // Execute this instruction against `machine`, leaving the program counter
+    // on the next instruction to run. Returns a state when the machine needs
+    // to hand control back to its caller.
+    pub fn exec(&self, machine: &mut Machine) -> Result<Option<State>, Error> {
+        let mut state = None;
+        let mut next_pc = machine.pc + self.nargs();
+        match self {
+            OpCode::Add(args) => {
+                let (a, b) = (machine.param(args, 1)?, machine.param(args, 2)?);
+                let value = machine.checked(a.checked_add(b))?;
+                machine.write(args, 3, value)?;
+            }
+            OpCode::Mul(args) => {
+                let (a, b) = (machine.param(args, 1)?, machine.param(args, 2)?);
+                let value = machine.checked(a.checked_mul(b))?;
+                machine.write(args, 3, value)?;
+            }
+            OpCode::Input(args) => match machine.input.pop_front() {
+                Some(value) => machine.write(args, 1, value)?,
+                None => return Ok(Some(State::NeedInput)),
+            },
+            OpCode::Output(args) => {
+                state = Some(State::Output(machine.param(args, 1)?));
+            }
+            OpCode::JumpIfTrue(args) => {
+                if machine.param(args, 1)? != 0 {
+                    next_pc = machine.jump_target(machine.param(args, 2)?)?;
+                }
+            }
+            OpCode::JumpIfFalse(args) => {
+                if machine.param(args, 1)? == 0 {
+                    next_pc = machine.jump_target(machine.param(args, 2)?)?;
+                }
+            }
+            OpCode::LessThan(args) => {
+                let value = machine.param(args, 1)? < machine.param(args, 2)?;
+                machine.write(args, 3, value as i64)?;
+            }
+            OpCode::Equals(args) => {
+                let value =
+                    machine.param(args, 1)? == machine.param(args, 2)?;
+                machine.write(args, 3, value as i64)?;
+            }
+            OpCode::AdjustBase(args) => {
+                machine.relative_base =
+                    machine.relative(machine.param(args, 1)?)?;
+            }
+            OpCode::Halt => return Ok(Some(State::Halted)),
+        }
+        machine.pc = next_pc;
+        Ok(state)
+    }
+}
+
+// An Intcode machine that can be paused whenever it produces output or runs
+// out of input, so devices can be attached to it.
+#[derive(Clone, Debug)]
+pub struct Machine {
+    memory: Vec<i64>,
+    pc: usize,
+    relative_base: i64,
+    input: VecDeque<i64>,
+}
+
+impl Machine {
+    pub fn new(program: Vec<i64>) -> Machine {
+        Machine {
+            memory: program,
+            pc: 0,
+            relative_base: 0,
+            input: VecDeque::new(),
+        }
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn memory(&self) -> &[i64] {
+        &self.memory
+    }
+
+    pub fn into_memory(self) -> Vec<i64> {
+        self.memory
+    }
+
+    pub fn step(&mut self) -> Result<Option<State>, Error> {
+        OpCode::new(self.pc, &self.memory)?.exec(self)
+    }
+
+    // Run until the program halts, produces a value or blocks on input.
+    pub fn run(&mut self) -> Result<State, Error> {
+        loop {
+            if let Some(state) = self.step()? {
+                return Ok(state);
+            }
+        }
+    }
+
+    fn mode(args: &[i64], i: usize) -> i64 {
+        args[0] / 10_i64.pow(i as u32 + 1) % 10
+    }
+
+    fn address(&self, args: &[i64], i: usize) -> Result<usize, Error> {
+        let address = match Self::mode(args, i) {
+            0 => args[i],
+            1 => return Err(Error::ImmediateWrite { pc: self.pc }),
+            2 => self.relative(args[i])?,
+            mode => return Err(Error::UnknownMode { pc: self.pc, mode }),
+        };
+        self.jump_target(address)
+    }
+
+    fn relative(&self, offset: i64) -> Result<i64, Error> {
+        self.checked(self.relative_base.checked_add(offset))
+    }
+
+    fn checked(&self, value: Option<i64>) -> Result<i64, Error> {
+        value.ok_or(Error::Overflow { pc: self.pc })
+    }
+
+    fn jump_target(&self, address: i64) -> Result<usize, Error> {
+        if address < 0 {
+            Err(Error::NegativeAddress {
+                pc: self.pc,
+                address,
+            })
+        } else {
+            Ok(address as usize)
+        }
+    }
+
+    fn param(&self, args: &[i64], i: usize) -> Result<i64, Error> {
+        if Self::mode(args, i) == 1 {
+            return Ok(args[i]);
+        }
+        let address = self.address(args, i)?;
+        Ok(self.memory.get(address).copied().unwrap_or(0))
+    }
+
+    fn write(
+        &mut self,
+        args: &[i64],
+        i: usize,
+        value: i64,
+    ) -> Result<(), Error> {
+        let address = self.address(args, i)?;
+        self.store(address, value)
+    }
+
+    fn store(&mut self, address: usize, value: i64) -> Result<(), Error> {
+        if address >= MAX_MEMORY {
+            return Err(Error::AddressTooLarge {
+                pc: self.pc,
+                address,
+            });
+        }
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = value;
+        Ok(())
+    }
+}
+
+// Why `interpret_with` stopped running the program.
+#[derive(Debug, PartialEq)]
+pub enum Stop {
+    Halted,
+    NeedInput,
+    LimitExceeded,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    pub memory: Vec<i64>,
+    pub output: Vec<i64>,
+    pub steps: u64,
+    pub stop: Stop,
+}
+
+// Run `program` on the given input until it halts, asks for more input than
+// it was given, or has executed `max_steps` instructions.
+pub fn interpret_with(
+    program: Vec<i64>,
+    input: &[i64],
+    max_steps: Option<u64>,
+) -> Result<Outcome, Error> {
+    let mut machine = Machine::new(program);
+    for &value in input {
+        machine.push_input(value);
+    }
+
+    let mut output = Vec::new();
+    let mut steps = 0;
+    let stop = loop {
+        if max_steps.is_some_and(|max| steps >= max) {
+            break Stop::LimitExceeded;
+        }
+        match machine.step()? {
+            None => {}
+            Some(State::Output(value)) => output.push(value),
+            Some(State::NeedInput) => break Stop::NeedInput,
+            Some(State::Halted) => break Stop::Halted,
+        }
+        steps += 1;
+    };
+
+    Ok(Outcome {
+        memory: machine.into_memory(),
+        output,
+        steps,
+        stop,
+    })
+}
+
+pub fn interpret(program: Vec<i64>) -> Vec<i64> {
+    match interpret_with(program, &[], None) {
+        Ok(Outcome {
+            stop: Stop::Halted,
+            memory,
+            ..
+        }) => memory,
+        Ok(_) => panic!("Program requested input"),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+// Find the noun and verb, the values at addresses 1 and 2, that make the
+// program leave `target` at address 0. Patched programs that fail or don't
+// halt within `max_steps` are skipped, as are programs too short to have a
+// noun and verb.
+pub fn search(
+    program: &[i64],
+    target: i64,
+    max_steps: u64,
+) -> Option<(i64, i64)> {
+    if program.len() < 3 {
+        return None;
+    }
+    for noun in 0..=99 {
+        for verb in 0..=99 {
+            let mut program = program.to_vec();
+            program[1] = noun;
+            program[2] = verb;
+
+            let outcome = interpret_with(program, &[], Some(max_steps));
+            if let Ok(Outcome {
+                stop: Stop::Halted,
+                memory,
+                ..
+            }) = outcome
+            {
+                if memory[0] == target {
+                    return Some((noun, verb));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test1() {
+        let input = vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+        let result = vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50];
+        println!("{:?}", input);
+        assert_eq!(interpret(input), result);
+    }
+
+    #[test]
+    fn test2() {
+        let input = vec![1, 0, 0, 0, 99];
+        let result = vec![2, 0, 0, 0, 99];
+        println!("{:?}", input);
+        assert_eq!(interpret(input), result);
+    }
+
+    #[test]
+    fn test3() {
+        let input = vec![2, 3, 0, 3, 99];
+        let result = vec![2, 3, 0, 6, 99];
+        println!("{:?}", input);
+        assert_eq!(interpret(input), result);
+    }
+
+    #[test]
+    fn test4() {
+        let input = vec![2, 4, 4, 5, 99, 0];
+        let result = vec![2, 4, 4, 5, 99, 9801];
+        println!("{:?}", input);
+        assert_eq!(interpret(input), result);
+    }
+
+    #[test]
+    fn test5() {
+        let input = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        let result = vec![30, 1, 1, 4, 2, 5, 6, 0, 99];
+        println!("{:?}", input);
+        assert_eq!(interpret(input), result);
+    }
+
+    #[test]
+    fn test_io() {
+        // echo the input back, compare it to 8 and output the result
+        let program = vec![3, 13, 4, 13, 1008, 13, 8, 13, 104, 0, 4, 13, 99];
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.run(), Ok(State::NeedInput));
+        machine.push_input(8);
+        assert_eq!(machine.run(), Ok(State::Output(8)));
+        assert_eq!(machine.run(), Ok(State::Output(0)));
+        assert_eq!(machine.run(), Ok(State::Output(1)));
+        assert_eq!(machine.run(), Ok(State::Halted));
+    }
+
+    #[test]
+    fn test_quine() {
+        let program = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        let mut machine = Machine::new(program.clone());
+        let mut output = Vec::new();
+        while let Ok(State::Output(value)) = machine.run() {
+            output.push(value);
+        }
+        assert_eq!(output, program);
+    }
+
+    #[test]
+    fn test_interpret_with() {
+        let program = vec![3, 9, 4, 9, 1105, 1, 0, 99, 0, 0];
+        let outcome = interpret_with(program.clone(), &[1, 2], None).unwrap();
+        assert_eq!(outcome.output, vec![1, 2]);
+        assert_eq!(outcome.steps, 6);
+        assert_eq!(outcome.stop, Stop::NeedInput);
+
+        let outcome = interpret_with(program, &[1, 2], Some(4)).unwrap();
+        assert_eq!(outcome.output, vec![1]);
+        assert_eq!(outcome.stop, Stop::LimitExceeded);
+    }
+
+    #[test]
+    fn test_search() {
+        // memory[0] = noun * verb
+        let program = vec![1102, 0, 0, 0, 99];
+        assert_eq!(search(&program, 12, 10), Some((1, 12)));
+        assert_eq!(search(&program, 9802, 10), None);
+        assert_eq!(search(&[99], 99, 10), None);
+        assert_eq!(search(&[], 0, 10), None);
+    }
+
+    #[test]
+    fn test_unknown_opcode() {
+        let mut machine = Machine::new(vec![1, 0, 0, 0, 42]);
+        assert_eq!(
+            machine.run(),
+            Err(Error::UnknownOpCode { pc: 4, opcode: 42 })
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut machine = Machine::new(vec![1102, i64::MAX, 2, 0, 99]);
+        assert_eq!(machine.run(), Err(Error::Overflow { pc: 0 }));
+    }
+
+    #[test]
+    fn test_address_too_large() {
+        let address = MAX_MEMORY as i64;
+        let mut machine = Machine::new(vec![1101, 1, 1, address, 99]);
+        assert_eq!(
+            machine.run(),
+            Err(Error::AddressTooLarge {
+                pc: 0,
+                address: MAX_MEMORY
+            })
+        );
+    }
+}
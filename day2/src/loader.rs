@@ -0,0 +1,379 @@
+// Reading program images from disk. Three formats are recognised:
+//
+// - text: integers separated by commas and/or whitespace, spread over any
+//   number of lines, with `#` starting a comment that runs to the end of the
+//   line
+// - binary: the `VARINT_MAGIC` header followed by one zigzag encoded LEB128
+//   varint per cell, which keeps large images with small values compact
+// - gzip: either of the above compressed with gzip
+//
+// The format is picked from the leading bytes, not the file name.
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+
+use crate::MAX_MEMORY;
+
+pub const VARINT_MAGIC: &[u8] = b"\0ICV";
+
+// Compressed images may expand to at most this many bytes, enough for a full
+// memory of the widest text cells, "-9223372036854775808,".
+pub const MAX_DECOMPRESSED: u64 = MAX_MEMORY as u64 * 21;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    // line and column are 1-based, the column counting characters
+    Parse {
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    Utf8 {
+        offset: usize,
+    },
+    Varint {
+        offset: usize,
+        reason: &'static str,
+    },
+    Gzip(io::Error),
+    // the image would be more than `limit` of `unit`, decompressed bytes or
+    // memory cells
+    TooLarge {
+        limit: u64,
+        unit: &'static str,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Parse {
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "Invalid value `{}` at line {}, column {}",
+                token, line, column
+            ),
+            LoadError::Utf8 { offset } => {
+                write!(f, "Invalid UTF-8 at byte {}", offset)
+            }
+            LoadError::Varint { offset, reason } => {
+                write!(f, "{} at byte {}", reason, offset)
+            }
+            LoadError::Gzip(e) => write!(f, "Bad gzip data: {}", e),
+            LoadError::TooLarge { limit, unit } => {
+                write!(f, "Image is more than {} {}", limit, unit)
+            }
+        }
+    }
+}
+
+impl error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<i64>, LoadError> {
+    parse(&fs::read(path)?)
+}
+
+// Parse an image in any of the supported formats.
+pub fn parse(data: &[u8]) -> Result<Vec<i64>, LoadError> {
+    if is_gzip(data) {
+        let data = decompress(data, MAX_DECOMPRESSED)?;
+        // a compressed image never holds another compressed image
+        if is_gzip(&data) {
+            return Err(LoadError::Gzip(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Nested gzip data",
+            )));
+        }
+        parse(&data)
+    } else if data.starts_with(VARINT_MAGIC) {
+        parse_varint(data)
+    } else {
+        match std::str::from_utf8(data) {
+            Ok(text) => parse_text(text),
+            Err(e) => Err(LoadError::Utf8 {
+                offset: e.valid_up_to(),
+            }),
+        }
+    }
+}
+
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&[0x1f, 0x8b])
+}
+
+// Decompress every gzip member in `data`, failing rather than producing more
+// than `limit` bytes.
+fn decompress(data: &[u8], limit: u64) -> Result<Vec<u8>, LoadError> {
+    let mut out = Vec::new();
+    MultiGzDecoder::new(data)
+        .take(limit + 1)
+        .read_to_end(&mut out)
+        .map_err(LoadError::Gzip)?;
+    if out.len() as u64 > limit {
+        return Err(LoadError::TooLarge {
+            limit,
+            unit: "bytes",
+        });
+    }
+    Ok(out)
+}
+
+// Images must fit in memory, so stop before reading a cell past its end.
+fn push_cell(program: &mut Vec<i64>, value: i64) -> Result<(), LoadError> {
+    if program.len() >= MAX_MEMORY {
+        return Err(LoadError::TooLarge {
+            limit: MAX_MEMORY as u64,
+            unit: "cells",
+        });
+    }
+    program.push(value);
+    Ok(())
+}
+
+pub fn parse_text(text: &str) -> Result<Vec<i64>, LoadError> {
+    let mut program = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let separator = |c: char| c == ',' || c.is_whitespace();
+
+        let mut chars = line.char_indices().enumerate().peekable();
+        while let Some((column, (start, c))) = chars.next() {
+            if separator(c) {
+                continue;
+            }
+            let mut end = start + c.len_utf8();
+            while let Some(&(_, (i, c))) = chars.peek() {
+                if separator(c) {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+
+            let token = &line[start..end];
+            match token.parse() {
+                Ok(value) => push_cell(&mut program, value)?,
+                Err(_) => {
+                    return Err(LoadError::Parse {
+                        line: n + 1,
+                        column: column + 1,
+                        token: token.to_string(),
+                    })
+                }
+            }
+        }
+    }
+    Ok(program)
+}
+
+pub fn parse_varint(data: &[u8]) -> Result<Vec<i64>, LoadError> {
+    if !data.starts_with(VARINT_MAGIC) {
+        return Err(LoadError::Varint {
+            offset: 0,
+            reason: "Missing varint header",
+        });
+    }
+
+    let mut program = Vec::new();
+    let mut pos = VARINT_MAGIC.len();
+    while pos < data.len() {
+        let start = pos;
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = match data.get(pos) {
+                Some(&byte) => byte,
+                None => {
+                    return Err(LoadError::Varint {
+                        offset: start,
+                        reason: "Truncated varint",
+                    })
+                }
+            };
+            // the tenth byte may only carry the top bit of a 64-bit value
+            if shift == 63 && byte > 1 {
+                return Err(LoadError::Varint {
+                    offset: pos,
+                    reason: "Varint too large",
+                });
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            pos += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        push_cell(&mut program, ((value >> 1) as i64) ^ -((value & 1) as i64))?;
+    }
+    Ok(program)
+}
+
+pub fn encode_varint(program: &[i64]) -> Vec<u8> {
+    let mut data = VARINT_MAGIC.to_vec();
+    for &cell in program {
+        let mut value = ((cell << 1) ^ (cell >> 63)) as u64;
+        while value >= 0x80 {
+            data.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        data.push(value as u8);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        let text = "# add two numbers\n\
+                    1, 5, 6, 0,   # sum goes to 0\n\
+                    99\n\
+                    \n\
+                    -3 4\t7";
+        assert_eq!(parse_text(text).unwrap(), vec![1, 5, 6, 0, 99, -3, 4, 7]);
+        assert_eq!(parse(b"1,0,0,3,99\n").unwrap(), vec![1, 0, 0, 3, 99]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        match parse_text("1,2,3\n4, 5x ,6") {
+            Err(LoadError::Parse {
+                line,
+                column,
+                token,
+            }) => assert_eq!((line, column, token.as_str()), (2, 4, "5x")),
+            other => panic!("unexpected {:?}", other),
+        }
+        let e = parse_text("1,2,99999999999999999999").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Invalid value `99999999999999999999` at line 1, column 5"
+        );
+        match parse(b"1,2,\xff") {
+            Err(LoadError::Utf8 { offset }) => assert_eq!(offset, 4),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_varint() {
+        let program = vec![0, 1, -1, 63, -64, 64, 1 << 40, i64::MAX, i64::MIN];
+        let data = encode_varint(&program);
+        assert_eq!(&data[4..8], &[0, 2, 1, 126]);
+        assert_eq!(parse(&data).unwrap(), program);
+
+        let mut truncated = encode_varint(&[1 << 20]);
+        truncated.pop();
+        match parse(&truncated) {
+            Err(LoadError::Varint { offset, reason }) => {
+                assert_eq!((offset, reason), (4, "Truncated varint"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut too_large = VARINT_MAGIC.to_vec();
+        too_large.extend_from_slice(&[0xff; 9]);
+        too_large.push(2);
+        assert!(parse(&too_large).is_err());
+    }
+
+    #[test]
+    fn test_too_many_cells() {
+        let full = "0,".repeat(MAX_MEMORY);
+        assert_eq!(parse_text(&full).unwrap().len(), MAX_MEMORY);
+        let e = parse_text(&(full + "0")).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!("Image is more than {} cells", MAX_MEMORY)
+        );
+
+        let mut full = VARINT_MAGIC.to_vec();
+        full.resize(VARINT_MAGIC.len() + MAX_MEMORY, 0);
+        assert_eq!(parse_varint(&full).unwrap().len(), MAX_MEMORY);
+        full.push(0);
+        match parse(&full) {
+            Err(LoadError::TooLarge { limit, unit }) => {
+                assert_eq!((limit, unit), (MAX_MEMORY as u64, "cells"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    // gzip.compress(b"1,0,0,3,99\n", mtime=0)
+    const FIXED: [u8; 29] = [
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 51, 212, 49, 0, 66, 99, 29, 75, 75,
+        46, 0, 209, 121, 105, 70, 11, 0, 0, 0,
+    ];
+
+    #[test]
+    fn test_gzip() {
+        let program = parse(include_bytes!("../input.txt.gz")).unwrap();
+        let text = include_str!("../input.txt");
+        assert_eq!(program, parse_text(text).unwrap());
+        assert_eq!(program[..4], [1, 12, 2, 3]);
+
+        assert_eq!(parse(&FIXED).unwrap(), vec![1, 0, 0, 3, 99]);
+        // two members concatenated
+        let twice = [&FIXED[..], &FIXED[..]].concat();
+        assert_eq!(
+            decompress(&twice, 100).unwrap(),
+            b"1,0,0,3,99\n1,0,0,3,99\n"
+        );
+    }
+
+    #[test]
+    fn test_gzip_errors() {
+        let mut corrupt = FIXED.to_vec();
+        corrupt[24] ^= 1;
+        assert!(matches!(parse(&corrupt), Err(LoadError::Gzip(_))));
+        assert!(matches!(parse(&FIXED[..20]), Err(LoadError::Gzip(_))));
+        assert!(matches!(parse(&FIXED[..5]), Err(LoadError::Gzip(_))));
+    }
+
+    #[test]
+    fn test_gzip_limit() {
+        assert_eq!(decompress(&FIXED, 11).unwrap(), b"1,0,0,3,99\n");
+        match decompress(&FIXED, 10) {
+            Err(LoadError::TooLarge { limit, unit }) => {
+                assert_eq!((limit, unit), (10, "bytes"))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // a megabyte of zeros squeezed into about a kilobyte
+        let mut bomb = flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::best(),
+        );
+        bomb.write_all(&[b'0'; 1 << 20]).unwrap();
+        let bomb = bomb.finish().unwrap();
+        assert!(bomb.len() < 4096);
+        assert!(matches!(
+            decompress(&bomb, 1 << 16),
+            Err(LoadError::TooLarge { .. })
+        ));
+    }
+}
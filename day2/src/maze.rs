@@ -0,0 +1,304 @@
+use std::collections::{HashMap, VecDeque};
+use std::error;
+use std::fmt;
+
+use day3::wire::WireVec;
+
+use crate::screen::{render_cells, TooLarge};
+use crate::{Machine, State};
+
+const DIRECTIONS: [WireVec; 4] = [
+    WireVec::Up(1),
+    WireVec::Down(1),
+    WireVec::Left(1),
+    WireVec::Right(1),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    // the droid answered a movement command with an unknown status
+    UnknownStatus { status: i64 },
+    // the droid hit a wall moving back the way it came
+    BacktrackFailed { position: (i64, i64) },
+    // the droid halted or wanted more input instead of answering a command
+    NoResponse { state: State },
+    Machine(crate::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownStatus { status } => {
+                write!(f, "Unknown droid status {}", status)
+            }
+            Error::BacktrackFailed { position } => {
+                write!(f, "Droid could not backtrack from {:?}", position)
+            }
+            Error::NoResponse { state } => {
+                write!(f, "Droid stopped responding: {:?}", state)
+            }
+            Error::Machine(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Error {
+        Error::Machine(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cell {
+    Wall,
+    Open,
+    Target,
+}
+
+// Everything the droid has learned about the area, relative to where it
+// started at (0, 0).
+#[derive(Debug, Default)]
+pub struct Map {
+    cells: HashMap<(i64, i64), Cell>,
+}
+
+impl Map {
+    pub fn get(&self, position: (i64, i64)) -> Option<Cell> {
+        self.cells.get(&position).copied()
+    }
+
+    pub fn target(&self) -> Option<(i64, i64)> {
+        self.cells
+            .iter()
+            .find(|&(_, &cell)| cell == Cell::Target)
+            .map(|(&position, _)| position)
+    }
+
+    // breadth first search over every reachable non-wall cell
+    pub fn distances(&self, from: (i64, i64)) -> HashMap<(i64, i64), usize> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        if self.is_open(from) {
+            distances.insert(from, 0);
+            queue.push_back(from);
+        }
+
+        while let Some(position) = queue.pop_front() {
+            let d = distances[&position];
+            for &dir in &DIRECTIONS {
+                let next = dir.advance(position);
+                if self.is_open(next) && !distances.contains_key(&next) {
+                    distances.insert(next, d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    pub fn shortest_path(
+        &self,
+        from: (i64, i64),
+        to: (i64, i64),
+    ) -> Option<usize> {
+        self.distances(from).get(&to).copied()
+    }
+
+    // time for something spreading one cell per minute from `from` to fill
+    // every reachable cell
+    pub fn fill_time(&self, from: (i64, i64)) -> Option<usize> {
+        self.distances(from).values().max().copied()
+    }
+
+    pub fn render(&self) -> Result<String, TooLarge> {
+        render_cells(self.cells.keys().copied(), true, |position| {
+            match self.get(position) {
+                _ if position == (0, 0) => 'D',
+                Some(Cell::Wall) => '#',
+                Some(Cell::Open) => '.',
+                Some(Cell::Target) => 'O',
+                None => ' ',
+            }
+        })
+    }
+
+    fn is_open(&self, position: (i64, i64)) -> bool {
+        matches!(self.get(position), Some(Cell::Open) | Some(Cell::Target))
+    }
+}
+
+// Map the whole area reachable from the droid's starting position with a
+// depth first search, backtracking along the path taken whenever the droid
+// runs out of unexplored neighbours. `send` moves the droid in a direction
+// and returns its status code.
+pub fn explore_with<F>(mut send: F) -> Result<Map, Error>
+where
+    F: FnMut(WireVec) -> Result<i64, Error>,
+{
+    let mut map = Map::default();
+    let mut position = (0, 0);
+    let mut path: Vec<WireVec> = Vec::new();
+    map.cells.insert(position, Cell::Open);
+
+    loop {
+        let unexplored = DIRECTIONS
+            .iter()
+            .copied()
+            .find(|dir| map.get(dir.advance(position)).is_none());
+
+        if let Some(dir) = unexplored {
+            let next = dir.advance(position);
+            let cell = match send(dir)? {
+                0 => Cell::Wall,
+                1 => Cell::Open,
+                2 => Cell::Target,
+                status => return Err(Error::UnknownStatus { status }),
+            };
+            map.cells.insert(next, cell);
+            if cell != Cell::Wall {
+                position = next;
+                path.push(dir);
+            }
+        } else if let Some(dir) = path.pop() {
+            let back = dir.reverse();
+            match send(back)? {
+                0 => return Err(Error::BacktrackFailed { position }),
+                _ => position = back.advance(position),
+            }
+        } else {
+            return Ok(map);
+        }
+    }
+}
+
+pub fn explore(machine: &mut Machine) -> Result<Map, Error> {
+    explore_with(|dir| command(machine, dir))
+}
+
+// north, south, west and east are commands 1 through 4
+fn command(machine: &mut Machine, dir: WireVec) -> Result<i64, Error> {
+    machine.push_input(match dir {
+        WireVec::Up(_) => 1,
+        WireVec::Down(_) => 2,
+        WireVec::Left(_) => 3,
+        WireVec::Right(_) => 4,
+        _ => unreachable!("The droid only moves along the axes"),
+    });
+    match machine.run()? {
+        State::Output(status) => Ok(status),
+        state => Err(Error::NoResponse { state }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "\
+#######
+#D..#.#
+#.#.#.#
+#.#...#
+#.###O#
+#######";
+
+    // simulate a droid in MAZE, with the first row at the top
+    fn fake_droid() -> impl FnMut(WireVec) -> Result<i64, Error> {
+        let mut cells = HashMap::new();
+        let mut position = (0, 0);
+        for (row, line) in MAZE.lines().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                let p = (col as i64, -(row as i64));
+                if c == 'D' {
+                    position = p;
+                }
+                cells.insert(p, c);
+            }
+        }
+
+        move |dir| {
+            let next = dir.advance(position);
+            Ok(match cells[&next] {
+                '#' => 0,
+                c => {
+                    position = next;
+                    if c == 'O' {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn test_explore() {
+        let map = explore_with(fake_droid()).unwrap();
+        let target = map.target().unwrap();
+        assert_eq!(target, (4, -3));
+        assert_eq!(map.shortest_path((0, 0), target), Some(7));
+        assert_eq!(map.fill_time(target), Some(10));
+        assert_eq!(
+            map.render().unwrap(),
+            " ### # \n#D..#.#\n#.#.#.#\n#.#...#\n#.###O#\n #   # \n"
+        );
+    }
+
+    #[test]
+    fn test_misbehaving_droid() {
+        // answer every command with status 7
+        let mut machine = Machine::new(vec![3, 100, 104, 7, 1105, 1, 0]);
+        assert_eq!(
+            explore(&mut machine).unwrap_err(),
+            Error::UnknownStatus { status: 7 }
+        );
+
+        // move up once, then find walls everywhere, even on the way back
+        let program = vec![3, 100, 104, 1, 3, 100, 104, 0, 1105, 1, 4];
+        let mut machine = Machine::new(program);
+        assert_eq!(
+            explore(&mut machine).unwrap_err(),
+            Error::BacktrackFailed { position: (0, 1) }
+        );
+
+        let mut machine = Machine::new(vec![99]);
+        assert_eq!(
+            explore(&mut machine).unwrap_err(),
+            Error::NoResponse {
+                state: State::Halted
+            }
+        );
+
+        let mut machine = Machine::new(vec![3, 100, 42]);
+        assert_eq!(
+            explore(&mut machine).unwrap_err(),
+            Error::Machine(crate::Error::UnknownOpCode { pc: 2, opcode: 42 })
+        );
+
+        // read commands without ever answering them
+        let mut machine = Machine::new(vec![3, 100, 1105, 1, 0]);
+        assert_eq!(
+            explore(&mut machine).unwrap_err(),
+            Error::NoResponse {
+                state: State::NeedInput
+            }
+        );
+    }
+
+    #[test]
+    fn test_walled_in() {
+        // answer "wall" to every movement command forever
+        let program = vec![3, 100, 104, 0, 1105, 1, 0];
+        let mut machine = Machine::new(program);
+        let map = explore(&mut machine).unwrap();
+
+        assert_eq!(map.target(), None);
+        assert_eq!(map.get((0, 1)), Some(Cell::Wall));
+        assert_eq!(map.fill_time((0, 0)), Some(0));
+        assert_eq!(map.render().unwrap(), " # \n#D#\n # \n");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Machine, OpCode};
+
+#[derive(Debug)]
+pub struct Optimized {
+    pub image: Vec<i64>,
+    // addresses of the instructions that were rewritten
+    pub rewritten: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    Position(i64),
+    Immediate(i64),
+}
+
+// Rewrite instructions in `program` into cheaper equivalents: operands read
+// from cells that nothing ever writes become immediates, additions of zero
+// and multiplications by one become plain copies, and arithmetic on
+// constants is folded into a single constant store.
+//
+// Instructions are only touched when the program is simple enough to reason
+// about statically: every jump target must be an immediate, no parameter may
+// be relative, and code must not be overwritten before it runs. Anything else
+// is returned unchanged.
+pub fn optimize(program: &[i64]) -> Optimized {
+    let mut image = program.to_vec();
+    let mut rewritten = Vec::new();
+
+    if let Some(code) = decode(program) {
+        let mut reads = HashSet::new();
+        let mut writes = HashSet::new();
+        for op in code.values() {
+            let args = op.args();
+            let (inputs, output) = op.params();
+            for &i in inputs {
+                if Machine::mode(args, i) == 0 {
+                    reads.insert(args[i] as usize);
+                }
+            }
+            if let Some(i) = output {
+                writes.insert(args[i] as usize);
+            }
+        }
+
+        for (&pc, op) in &code {
+            let cells = pc..pc + op.args().len();
+            if cells
+                .clone()
+                .any(|c| reads.contains(&c) || writes.contains(&c))
+            {
+                continue;
+            }
+            if let Some(args) = rewrite(op, program, &writes) {
+                if args != op.args() {
+                    image[cells].copy_from_slice(&args);
+                    rewritten.push(pc);
+                }
+            }
+        }
+    }
+
+    Optimized { image, rewritten }
+}
+
+// Find every instruction reachable from pc 0. Returns None when control flow
+// or memory accesses can't be followed statically.
+fn decode(program: &[i64]) -> Option<BTreeMap<usize, OpCode>> {
+    let mut code = BTreeMap::new();
+    let mut pending = vec![0];
+    let mut has_jumps = false;
+
+    while let Some(pc) = pending.pop() {
+        if code.contains_key(&pc) {
+            continue;
+        }
+        let op = OpCode::new(pc, program).ok()?;
+        let args = op.args();
+        for i in 1..args.len() {
+            match Machine::mode(args, i) {
+                0 if args[i] < 0 => return None,
+                0 | 1 => {}
+                _ => return None,
+            }
+        }
+        if let Some(i) = op.params().1 {
+            if Machine::mode(args, i) != 0 {
+                return None;
+            }
+        }
+
+        match op {
+            OpCode::Halt => {}
+            OpCode::JumpIfTrue(ref args) | OpCode::JumpIfFalse(ref args) => {
+                if Machine::mode(args, 2) != 1 || args[2] < 0 {
+                    return None;
+                }
+                has_jumps = true;
+                pending.push(args[2] as usize);
+                pending.push(pc + op.nargs());
+            }
+            _ => pending.push(pc + op.nargs()),
+        }
+        code.insert(pc, op);
+    }
+
+    // instructions must not overlap
+    let mut end = 0;
+    for (&pc, op) in &code {
+        if pc < end {
+            return None;
+        }
+        end = pc + op.args().len();
+    }
+
+    // Code may only be overwritten once it can never run again, which is
+    // only easy to show for straight-line programs writing behind themselves.
+    for (&pc, op) in &code {
+        if let (Some(i), args) = (op.params().1, op.args()) {
+            let target = args[i] as usize;
+            let hit = code.range(..=target).next_back();
+            if let Some((&start, other)) = hit {
+                let overwrites_code = target < start + other.args().len();
+                if overwrites_code && (has_jumps || start > pc) {
+                    return None;
+                }
+            }
+        }
+    }
+
+    Some(code)
+}
+
+fn rewrite(
+    op: &OpCode,
+    program: &[i64],
+    writes: &HashSet<usize>,
+) -> Option<Vec<i64>> {
+    if let OpCode::Input(_) = op {
+        return None;
+    }
+    let args = op.args();
+    let (inputs, output) = op.params();
+    let operands: Vec<Operand> = inputs
+        .iter()
+        .map(|&i| match Machine::mode(args, i) {
+            0 if !writes.contains(&(args[i] as usize)) => {
+                let value = program.get(args[i] as usize).copied();
+                Operand::Immediate(value.unwrap_or(0))
+            }
+            0 => Operand::Position(args[i]),
+            _ => Operand::Immediate(args[i]),
+        })
+        .collect();
+    let opcode = args[0] % 100;
+
+    let output = match output {
+        Some(i) => args[i],
+        None => return Some(encode(opcode, &operands)),
+    };
+    let operands = match (op, operands[0], operands[1]) {
+        (_, Operand::Immediate(a), Operand::Immediate(b)) => {
+            let value = match op {
+                OpCode::Add(_) => a.checked_add(b)?,
+                OpCode::Mul(_) => a.checked_mul(b)?,
+                OpCode::LessThan(_) => (a < b) as i64,
+                _ => (a == b) as i64,
+            };
+            return Some(encode(
+                1,
+                &[Operand::Immediate(value), zero(), out(output)],
+            ));
+        }
+        (OpCode::Add(_), Operand::Immediate(0), x)
+        | (OpCode::Add(_), x, Operand::Immediate(0))
+        | (OpCode::Mul(_), Operand::Immediate(1), x)
+        | (OpCode::Mul(_), x, Operand::Immediate(1)) => {
+            return Some(encode(1, &[x, zero(), out(output)]));
+        }
+        (OpCode::Mul(_), Operand::Immediate(0), _)
+        | (OpCode::Mul(_), _, Operand::Immediate(0)) => {
+            return Some(encode(1, &[zero(), zero(), out(output)]));
+        }
+        (_, a, b) => [a, b, out(output)],
+    };
+    Some(encode(opcode, &operands))
+}
+
+fn zero() -> Operand {
+    Operand::Immediate(0)
+}
+
+fn out(address: i64) -> Operand {
+    Operand::Position(address)
+}
+
+fn encode(opcode: i64, operands: &[Operand]) -> Vec<i64> {
+    let mut word = opcode;
+    let mut cells = vec![0];
+    for (i, operand) in operands.iter().enumerate() {
+        match *operand {
+            Operand::Position(address) => cells.push(address),
+            Operand::Immediate(value) => {
+                word += 10_i64.pow(i as u32 + 2);
+                cells.push(value);
+            }
+        }
+    }
+    cells[0] = word;
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{day2_examples, day2_input};
+    use crate::{interpret, State};
+
+    fn run(program: Vec<i64>, input: &[i64]) -> (Vec<i64>, Vec<i64>) {
+        let mut machine = Machine::new(program);
+        for &value in input {
+            machine.push_input(value);
+        }
+        let mut output = Vec::new();
+        while let State::Output(value) = machine.run().unwrap() {
+            output.push(value);
+        }
+        (machine.into_memory(), output)
+    }
+
+    // Run both images and compare outputs, and memory everywhere except the
+    // rewritten instructions.
+    fn differential(program: Vec<i64>, input: &[i64]) -> Optimized {
+        let optimized = optimize(&program);
+        let (expected, expected_out) = run(program.clone(), input);
+        let (actual, actual_out) = run(optimized.image.clone(), input);
+        assert_eq!(expected_out, actual_out);
+        assert_eq!(expected.len(), actual.len());
+
+        let code = decode(&program).unwrap_or_default();
+        let changed: HashSet<usize> = optimized
+            .rewritten
+            .iter()
+            .flat_map(|pc| *pc..*pc + code[pc].args().len())
+            .collect();
+        for (i, (a, b)) in expected.iter().zip(&actual).enumerate() {
+            if !changed.contains(&i) {
+                assert_eq!(a, b, "memory differs at {}", i);
+            }
+        }
+        optimized
+    }
+
+    #[test]
+    fn test_patterns() {
+        #[rustfmt::skip]
+        let program = vec![
+            3, 30,            // input -> [30]
+            1, 30, 25, 31,    // [30] + 0 -> [31]
+            2, 26, 31, 32,    // 1 * [31] -> [32]
+            1, 27, 28, 33,    // 5 + 7 -> [33]
+            2, 31, 25, 34,    // [31] * 0 -> [34]
+            4, 32,
+            4, 33,
+            4, 34,
+            99,
+            0, 1, 5, 7,
+        ];
+        let optimized = differential(program, &[42]);
+        assert_eq!(optimized.rewritten, vec![2, 6, 10, 14]);
+        assert_eq!(
+            optimized.image[2..18],
+            [
+                1001, 30, 0, 31, 1001, 31, 0, 32, 1101, 12, 0, 33, 1101, 0, 0,
+                34,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jumps() {
+        // count down from the input, outputting every value
+        #[rustfmt::skip]
+        let program = vec![
+            3, 20,             // input -> [20]
+            4, 20,             // output [20]
+            1, 20, 21, 20,     // [20] + [21] -> [20]
+            1005, 20, 2,       // loop while [20] != 0
+            2, 22, 23, 24,     // 9 * 1 -> [24]
+            4, 24,
+            99, 0, 0,
+            0, -1, 9, 1, 0,
+        ];
+        let optimized = differential(program, &[3]);
+        assert_eq!(optimized.rewritten, vec![4, 11]);
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // writes an instruction that hasn't run yet
+        let program = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        let optimized = differential(program, &[]);
+        assert!(optimized.rewritten.is_empty());
+    }
+
+    #[test]
+    fn test_day2_examples() {
+        for program in day2_examples() {
+            differential(program, &[]);
+        }
+    }
+
+    #[test]
+    fn test_day2_input() {
+        let program = day2_input();
+        for &(noun, verb) in &[(12, 2), (76, 10), (0, 0), (99, 99)] {
+            let mut program = program.clone();
+            program[1] = noun;
+            program[2] = verb;
+            let optimized = differential(program.clone(), &[]);
+            assert_eq!(interpret(optimized.image)[0], interpret(program)[0]);
+        }
+    }
+}
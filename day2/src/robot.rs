@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use day3::wire::WireVec;
+
+use crate::screen::{render_cells, TooLarge};
+use crate::{Machine, State};
+
+pub const BLACK: i64 = 0;
+pub const WHITE: i64 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    // the program told the robot to turn neither left (0) nor right (1)
+    UnknownTurn { turn: i64 },
+    Machine(crate::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownTurn { turn } => {
+                write!(f, "Unknown turn direction {}", turn)
+            }
+            Error::Machine(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<crate::Error> for Error {
+    fn from(e: crate::Error) -> Error {
+        Error::Machine(e)
+    }
+}
+
+// Panels that were never painted are black. The robot's heading is the
+// one-panel move it makes after each turn.
+#[derive(Debug)]
+pub struct Robot {
+    position: (i64, i64),
+    heading: WireVec,
+    panels: HashMap<(i64, i64), i64>,
+}
+
+impl Default for Robot {
+    fn default() -> Robot {
+        Robot {
+            position: (0, 0),
+            heading: WireVec::Up(1),
+            panels: HashMap::new(),
+        }
+    }
+}
+
+impl Robot {
+    pub fn new() -> Robot {
+        Default::default()
+    }
+
+    pub fn position(&self) -> (i64, i64) {
+        self.position
+    }
+
+    pub fn heading(&self) -> WireVec {
+        self.heading
+    }
+
+    pub fn colour(&self, position: (i64, i64)) -> i64 {
+        self.panels.get(&position).copied().unwrap_or(BLACK)
+    }
+
+    pub fn paint(&mut self, colour: i64) {
+        self.panels.insert(self.position, colour);
+    }
+
+    // Paint the current panel, then turn (0 is left, 1 is right) and move
+    // forward one panel.
+    pub fn step(&mut self, colour: i64, turn: i64) -> Result<(), Error> {
+        self.heading = match turn {
+            0 => self.heading.turn_left(),
+            1 => self.heading.turn_right(),
+            _ => return Err(Error::UnknownTurn { turn }),
+        };
+        self.paint(colour);
+        self.position = self.heading.advance(self.position);
+        Ok(())
+    }
+
+    // number of panels painted at least once, whatever their colour now
+    pub fn painted(&self) -> usize {
+        self.panels.len()
+    }
+
+    // +y is up, so the top row comes first
+    pub fn render(&self) -> Result<String, TooLarge> {
+        render_cells(self.panels.keys().copied(), true, |position| {
+            match self.colour(position) {
+                WHITE => '#',
+                _ => '.',
+            }
+        })
+    }
+}
+
+// Drive `robot` with `machine` until the program halts. The program is given
+// the colour of the panel under the robot whenever it asks for input and
+// answers with (colour, turn) pairs.
+pub fn run(machine: &mut Machine, robot: &mut Robot) -> Result<(), Error> {
+    let mut pending = Vec::with_capacity(2);
+    loop {
+        match machine.run()? {
+            State::Output(value) => {
+                pending.push(value);
+                if let &[colour, turn] = pending.as_slice() {
+                    robot.step(colour, turn)?;
+                    pending.clear();
+                }
+            }
+            State::NeedInput => {
+                machine.push_input(robot.colour(robot.position))
+            }
+            State::Halted => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // read a panel colour, ignore it and answer with the next pair
+    fn scripted(pairs: &[(i64, i64)]) -> Vec<i64> {
+        let mut program = Vec::new();
+        for &(colour, turn) in pairs {
+            program.extend_from_slice(&[3, 1000, 104, colour, 104, turn]);
+        }
+        program.push(99);
+        program
+    }
+
+    #[test]
+    fn test_example() {
+        let pairs = [(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)];
+        let mut machine = Machine::new(scripted(&pairs));
+        let mut robot = Robot::new();
+        run(&mut machine, &mut robot).unwrap();
+
+        assert_eq!(robot.painted(), 6);
+        assert_eq!(robot.position(), (0, 1));
+        assert_eq!(robot.heading(), WireVec::Left(1));
+        assert_eq!(robot.render().unwrap(), "..#\n..#\n##.\n");
+    }
+
+    #[test]
+    fn test_reads_panel_colour() {
+        // repaint the starting panel with whatever colour it already has
+        let program = vec![3, 1000, 4, 1000, 104, 1, 99];
+        let mut machine = Machine::new(program);
+        let mut robot = Robot::new();
+        robot.paint(WHITE);
+        run(&mut machine, &mut robot).unwrap();
+
+        assert_eq!(robot.colour((0, 0)), WHITE);
+        assert_eq!(robot.painted(), 1);
+    }
+
+    #[test]
+    fn test_machine_error() {
+        let mut machine = Machine::new(vec![3, 1000, 104, 1, 42]);
+        let mut robot = Robot::new();
+        assert_eq!(
+            run(&mut machine, &mut robot),
+            Err(Error::Machine(crate::Error::UnknownOpCode {
+                pc: 4,
+                opcode: 42
+            }))
+        );
+    }
+
+    #[test]
+    fn test_unknown_turn() {
+        let mut machine = Machine::new(scripted(&[(1, 0), (1, 2)]));
+        let mut robot = Robot::new();
+        assert_eq!(
+            run(&mut machine, &mut robot),
+            Err(Error::UnknownTurn { turn: 2 })
+        );
+        // the bad instruction paints nothing
+        assert_eq!(robot.painted(), 1);
+        assert_eq!(robot.position(), (-1, 0));
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+use std::io::prelude::*;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Error, Machine, State};
+
+// Drawing to this coordinate updates the score display instead of a tile.
+const SCORE_POSITION: (i64, i64) = (-1, 0);
+
+// ANSI escape sequence to clear the terminal and move the cursor home.
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+// Drawings wider or taller than this many cells aren't rendered, as programs
+// can draw cells arbitrarily far apart.
+pub const MAX_RENDER_SIZE: u64 = 1 << 12;
+
+// The cells of a drawing span more than `MAX_RENDER_SIZE` columns or rows.
+#[derive(Debug, PartialEq)]
+pub struct TooLarge {
+    pub width: u64,
+    pub height: u64,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Drawing of {} by {} cells is too large to render",
+            self.width, self.height
+        )
+    }
+}
+
+impl error::Error for TooLarge {}
+
+// Draw the bounding box of `positions` a row at a time, asking `glyph` for
+// each cell. Rows run down the page from the smallest y, or from the largest
+// when `y_up` is set.
+pub fn render_cells<I, F>(
+    positions: I,
+    y_up: bool,
+    glyph: F,
+) -> Result<String, TooLarge>
+where
+    I: IntoIterator<Item = (i64, i64)>,
+    F: Fn((i64, i64)) -> char,
+{
+    let mut out = String::new();
+    let mut positions = positions.into_iter();
+    let (x, y) = match positions.next() {
+        Some(position) => position,
+        None => return Ok(out),
+    };
+    let (min_x, max_x, min_y, max_y) = positions
+        .fold((x, x, y, y), |b, (x, y)| {
+            (b.0.min(x), b.1.max(x), b.2.min(y), b.3.max(y))
+        });
+
+    let width = max_x.abs_diff(min_x).saturating_add(1);
+    let height = max_y.abs_diff(min_y).saturating_add(1);
+    if width > MAX_RENDER_SIZE || height > MAX_RENDER_SIZE {
+        return Err(TooLarge { width, height });
+    }
+    for row in 0..height as i64 {
+        let y = if y_up { max_y - row } else { min_y + row };
+        for x in min_x..=max_x {
+            out.push(glyph((x, y)));
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// `TooLarge` for callers that are writing to the terminal anyway
+fn invalid(e: TooLarge) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    glyphs: HashMap<i64, char>,
+    unknown: char,
+}
+
+impl Palette {
+    pub fn new(unknown: char) -> Palette {
+        Palette {
+            glyphs: HashMap::new(),
+            unknown,
+        }
+    }
+
+    pub fn set(mut self, tile: i64, glyph: char) -> Palette {
+        self.glyphs.insert(tile, glyph);
+        self
+    }
+
+    pub fn glyph(&self, tile: i64) -> char {
+        self.glyphs.get(&tile).copied().unwrap_or(self.unknown)
+    }
+}
+
+// empty, wall, block, paddle, ball
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::new('?')
+            .set(0, ' ')
+            .set(1, '#')
+            .set(2, '=')
+            .set(3, '-')
+            .set(4, 'o')
+    }
+}
+
+// A snapshot of everything drawn to the screen so far. Only tiles that have
+// been drawn are stored, so programs can draw anywhere on the plane.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    tiles: HashMap<(i64, i64), i64>,
+    score: Option<i64>,
+}
+
+impl Frame {
+    pub fn get(&self, x: i64, y: i64) -> Option<i64> {
+        self.tiles.get(&(x, y)).copied()
+    }
+
+    pub fn score(&self) -> Option<i64> {
+        self.score
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = ((i64, i64), i64)> + '_ {
+        self.tiles.iter().map(|(&pos, &tile)| (pos, tile))
+    }
+
+    // position of some tile of the given kind, e.g. the ball
+    pub fn find(&self, tile: i64) -> Option<(i64, i64)> {
+        self.tiles().find(|&(_, t)| t == tile).map(|(pos, _)| pos)
+    }
+
+    pub fn count(&self, tile: i64) -> usize {
+        self.tiles().filter(|&(_, t)| t == tile).count()
+    }
+
+    pub fn render(&self, palette: &Palette) -> Result<String, TooLarge> {
+        let mut out =
+            render_cells(self.tiles.keys().copied(), false, |(x, y)| {
+                palette.glyph(self.get(x, y).unwrap_or(0))
+            })?;
+        if let Some(score) = self.score {
+            out.push_str(&format!("Score: {}\n", score));
+        }
+        Ok(out)
+    }
+}
+
+// Display device for programs that output (x, y, tile) triples.
+#[derive(Debug, Default)]
+pub struct Screen {
+    frame: Frame,
+    palette: Palette,
+    pending: Vec<i64>,
+    recording: Option<Vec<Frame>>,
+}
+
+impl Screen {
+    pub fn new() -> Screen {
+        Default::default()
+    }
+
+    pub fn with_palette(palette: Palette) -> Screen {
+        Screen {
+            palette,
+            ..Default::default()
+        }
+    }
+
+    // Keep a copy of the framebuffer every time `capture` is called.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        self.recording.as_deref().unwrap_or(&[])
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    // Accept one output value, drawing once a full triple has arrived.
+    pub fn feed(&mut self, value: i64) {
+        self.pending.push(value);
+        if let &[x, y, tile] = self.pending.as_slice() {
+            self.draw(x, y, tile);
+            self.pending.clear();
+        }
+    }
+
+    pub fn draw(&mut self, x: i64, y: i64, tile: i64) {
+        if (x, y) == SCORE_POSITION {
+            self.frame.score = Some(tile);
+        } else {
+            self.frame.tiles.insert((x, y), tile);
+        }
+    }
+
+    pub fn capture(&mut self) {
+        if let Some(frames) = self.recording.as_mut() {
+            frames.push(self.frame.clone());
+        }
+    }
+
+    pub fn render(&self) -> Result<String, TooLarge> {
+        self.frame.render(&self.palette)
+    }
+
+    pub fn show(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "{}{}", CLEAR, self.render().map_err(invalid)?)?;
+        out.flush()
+    }
+}
+
+pub fn replay(
+    frames: &[Frame],
+    palette: &Palette,
+    out: &mut impl Write,
+    delay: Duration,
+) -> io::Result<()> {
+    for frame in frames {
+        write!(out, "{}{}", CLEAR, frame.render(palette).map_err(invalid)?)?;
+        out.flush()?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+// Run `machine` until it halts, drawing its output to `screen`. Whenever the
+// program asks for input a frame is captured and `joystick` is consulted.
+pub fn play<F>(
+    machine: &mut Machine,
+    screen: &mut Screen,
+    mut joystick: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&Frame) -> i64,
+{
+    loop {
+        match machine.run()? {
+            State::Output(value) => screen.feed(value),
+            State::NeedInput => {
+                screen.capture();
+                machine.push_input(joystick(screen.frame()));
+            }
+            State::Halted => {
+                screen.capture();
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw() {
+        let mut screen = Screen::new();
+        for &value in &[0, 0, 1, 2, 0, 1, 1, 1, 4, -1, 0, 42] {
+            screen.feed(value);
+        }
+        assert_eq!(screen.frame().get(2, 0), Some(1));
+        assert_eq!(screen.frame().find(4), Some((1, 1)));
+        assert_eq!(screen.frame().score(), Some(42));
+        assert_eq!(screen.render().unwrap(), "# #\n o \nScore: 42\n");
+    }
+
+    #[test]
+    fn test_play() {
+        // draw whatever tile the joystick gives at (0, 0), then score 7
+        let program =
+            vec![3, 100, 104, 0, 104, 0, 4, 100, 104, -1, 104, 0, 104, 7, 99];
+        let mut machine = Machine::new(program);
+        let mut screen = Screen::new();
+        screen.start_recording();
+
+        play(&mut machine, &mut screen, |_| 4).unwrap();
+
+        assert_eq!(screen.frame().find(4), Some((0, 0)));
+        assert_eq!(screen.frame().score(), Some(7));
+        assert_eq!(screen.frames().len(), 2);
+        assert_eq!(screen.frames()[0], Frame::default());
+        assert_eq!(&screen.frames()[1], screen.frame());
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::new('.').set(1, 'X');
+        let mut screen = Screen::with_palette(palette);
+        screen.draw(0, 0, 1);
+        screen.draw(1, 0, 9);
+        assert_eq!(screen.render().unwrap(), "X.\n");
+    }
+
+    #[test]
+    fn test_too_large() {
+        // two tiles far apart are a valid drawing, just not one to render
+        let mut screen = Screen::new();
+        screen.draw(0, 0, 1);
+        screen.draw(i64::MAX / 2, 0, 1);
+        assert_eq!(
+            screen.render(),
+            Err(TooLarge {
+                width: i64::MAX as u64 / 2 + 1,
+                height: 1
+            })
+        );
+        assert!(screen.show(&mut Vec::new()).is_err());
+
+        // the full range of x saturates rather than overflowing
+        screen.draw(i64::MIN, i64::MAX, 1);
+        screen.draw(i64::MAX, 0, 1);
+        assert_eq!(
+            screen.render(),
+            Err(TooLarge {
+                width: u64::MAX,
+                height: i64::MAX as u64 + 1
+            })
+        );
+
+        let mut wide = Screen::new();
+        wide.draw(0, 0, 1);
+        wide.draw(MAX_RENDER_SIZE as i64 - 1, 0, 1);
+        assert_eq!(wide.render().unwrap().len(), MAX_RENDER_SIZE as usize + 1);
+        wide.draw(MAX_RENDER_SIZE as i64, 0, 1);
+        assert!(wide.render().is_err());
+    }
+
+    #[test]
+    fn test_render_cells() {
+        let cells = [(0, 0), (1, 2)];
+        let glyph = |p| if cells.contains(&p) { '#' } else { '.' };
+        assert_eq!(
+            render_cells(cells.iter().copied(), false, glyph).unwrap(),
+            "#.\n..\n.#\n"
+        );
+        assert_eq!(
+            render_cells(cells.iter().copied(), true, glyph).unwrap(),
+            ".#\n..\n#.\n"
+        );
+        assert_eq!(render_cells(vec![], true, glyph).unwrap(), "");
+    }
+}
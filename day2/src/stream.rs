@@ -0,0 +1,184 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::UnboundedSender;
+use futures::stream::{Stream, StreamExt};
+
+use crate::{Error, Machine, State};
+
+// A machine that reads its input from a stream and is itself a stream of the
+// values it outputs. The stream ends when the program halts, or after the
+// first error. Running out of input is an error only once the input stream
+// has finished; until then the machine waits for more to arrive.
+//
+// The machine runs synchronously between inputs and outputs, so a program
+// that loops without doing either will hold up the executor polling it.
+pub struct AsyncMachine<S> {
+    machine: Machine,
+    input: S,
+    done: bool,
+}
+
+impl<S> AsyncMachine<S>
+where
+    S: Stream<Item = i64> + Unpin,
+{
+    pub fn new(program: Vec<i64>, input: S) -> AsyncMachine<S> {
+        AsyncMachine {
+            machine: Machine::new(program),
+            input,
+            done: false,
+        }
+    }
+
+    pub fn memory(&self) -> &[i64] {
+        self.machine.memory()
+    }
+}
+
+impl<S> Stream for AsyncMachine<S>
+where
+    S: Stream<Item = i64> + Unpin,
+{
+    type Item = Result<i64, Error>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            match this.machine.run() {
+                Ok(State::Output(value)) => {
+                    return Poll::Ready(Some(Ok(value)))
+                }
+                Ok(State::Halted) => {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+                Ok(State::NeedInput) => match this.input.poll_next_unpin(cx) {
+                    Poll::Ready(Some(value)) => this.machine.push_input(value),
+                    Poll::Ready(None) => {
+                        this.done = true;
+                        let pc = this.machine.pc();
+                        return Poll::Ready(Some(Err(Error::InputClosed {
+                            pc,
+                        })));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+// Drive `machine` to completion, sending each value it outputs down `tx` and
+// returning them all once it halts. Values sent after the receiving end has
+// gone away are dropped, which lets the last machine in a loop outlive the
+// first.
+pub async fn forward<S>(
+    mut machine: AsyncMachine<S>,
+    tx: UnboundedSender<i64>,
+) -> Result<Vec<i64>, Error>
+where
+    S: Stream<Item = i64> + Unpin,
+{
+    let mut output = Vec::new();
+    while let Some(value) = machine.next().await {
+        let value = value?;
+        let _ = tx.unbounded_send(value);
+        output.push(value);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{ring, CHAIN, FEEDBACK_LOOP};
+    use futures::channel::mpsc;
+    use futures::executor::{block_on, LocalPool};
+    use futures::future::try_join_all;
+    use futures::stream;
+    use futures::task::LocalSpawnExt;
+
+    // the last value out of a ring of amplifiers
+    fn amplify(program: &[i64], phases: &[i64]) -> i64 {
+        let send = |tx: &mpsc::UnboundedSender<i64>, value| {
+            tx.unbounded_send(value).unwrap()
+        };
+        let amplifiers: Vec<_> = ring(phases, mpsc::unbounded, send)
+            .into_iter()
+            .map(|(rx, next)| {
+                forward(AsyncMachine::new(program.to_vec(), rx), next)
+            })
+            .collect();
+
+        let outputs = block_on(try_join_all(amplifiers)).unwrap();
+        *outputs.last().unwrap().last().unwrap()
+    }
+
+    #[test]
+    fn test_stream() {
+        // double every input
+        let program = vec![3, 11, 1002, 11, 2, 11, 4, 11, 1105, 1, 0];
+        let input = stream::iter(vec![1, 2, 3]);
+        let output: Vec<_> =
+            block_on(AsyncMachine::new(program, input).collect());
+        assert_eq!(
+            output,
+            vec![Ok(2), Ok(4), Ok(6), Err(Error::InputClosed { pc: 0 })]
+        );
+    }
+
+    #[test]
+    fn test_chain() {
+        assert_eq!(amplify(CHAIN.program, &CHAIN.phases), CHAIN.signal);
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        assert_eq!(
+            amplify(FEEDBACK_LOOP.program, &FEEDBACK_LOOP.phases),
+            FEEDBACK_LOOP.signal
+        );
+    }
+
+    #[test]
+    fn test_local_pool() {
+        // a producer task feeding a machine through a channel
+        let mut pool = LocalPool::new();
+        let (tx, rx) = mpsc::unbounded();
+        let (out_tx, out_rx) = mpsc::unbounded();
+        pool.spawner()
+            .spawn_local(async move {
+                for value in 1..=3 {
+                    tx.unbounded_send(value).unwrap();
+                }
+            })
+            .unwrap();
+        let program = vec![3, 11, 1002, 11, 2, 11, 4, 11, 1105, 1, 0];
+        let machine = AsyncMachine::new(program, rx);
+        let result = pool.run_until(forward(machine, out_tx));
+        assert_eq!(result, Err(Error::InputClosed { pc: 0 }));
+        let output: Vec<i64> = pool.run_until(out_rx.collect());
+        assert_eq!(output, vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn test_error() {
+        let machine = AsyncMachine::new(vec![42], stream::empty());
+        let output: Vec<_> = block_on(machine.collect());
+        assert_eq!(
+            output,
+            vec![Err(Error::UnknownOpCode { pc: 0, opcode: 42 })]
+        );
+    }
+}
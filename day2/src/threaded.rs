@@ -0,0 +1,412 @@
+use std::error;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::{Error, Machine, State};
+
+// Why a network of threaded machines stopped early.
+#[derive(Debug, PartialEq)]
+pub enum Failure {
+    Machine { id: usize, error: Error },
+    Panicked { id: usize },
+    // every live machine was waiting on an empty channel
+    Deadlock { waiting: Vec<usize> },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Machine { id, error } => {
+                write!(f, "Machine {} failed: {}", id, error)
+            }
+            Failure::Panicked { id } => write!(f, "Machine {} panicked", id),
+            Failure::Deadlock { waiting } => {
+                write!(f, "Deadlock with machines {:?} waiting", waiting)
+            }
+        }
+    }
+}
+
+impl error::Error for Failure {}
+
+// Everything the machines and the supervisor coordinate through. All sends
+// into the network happen with the lock held, which is what makes deadlock
+// detection exact: a machine only counts as blocked once it has seen its
+// channel empty, and any send since then bumps `epoch` and wakes it to look
+// again.
+struct Status {
+    epoch: u64,
+    alive: Vec<bool>,
+    // the epoch at which each machine last found its input empty
+    blocked: Vec<Option<u64>>,
+    shutdown: bool,
+    failure: Option<Failure>,
+}
+
+impl Status {
+    fn fail(&mut self, failure: Failure) {
+        if self.failure.is_none() {
+            self.failure = Some(failure);
+        }
+    }
+
+    fn live(&self) -> Vec<usize> {
+        (0..self.alive.len()).filter(|&i| self.alive[i]).collect()
+    }
+
+    fn deadlocked(&self) -> bool {
+        let live = self.live();
+        !live.is_empty()
+            && live.iter().all(|&i| self.blocked[i] == Some(self.epoch))
+    }
+}
+
+struct Shared {
+    status: Mutex<Status>,
+    changed: Condvar,
+}
+
+impl Shared {
+    // Wait for a value on `input`, or return None if the network is shutting
+    // down or the channel has been closed.
+    fn receive(
+        &self,
+        id: usize,
+        pc: usize,
+        input: &Receiver<i64>,
+    ) -> Option<i64> {
+        let mut status = self.status.lock().unwrap();
+        loop {
+            if status.shutdown {
+                return None;
+            }
+            match input.try_recv() {
+                Ok(value) => {
+                    status.blocked[id] = None;
+                    return Some(value);
+                }
+                Err(TryRecvError::Disconnected) => {
+                    let error = Error::InputClosed { pc };
+                    status.fail(Failure::Machine { id, error });
+                    self.changed.notify_all();
+                    return None;
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+            if status.blocked[id] != Some(status.epoch) {
+                status.blocked[id] = Some(status.epoch);
+                self.changed.notify_all();
+            }
+            status = self.changed.wait(status).unwrap();
+        }
+    }
+}
+
+// The sending half of a channel into the network. Plain `mpsc::Sender`s
+// would work for moving values, but the supervisor needs to hear about every
+// send to tell a deadlock from a machine that's about to be woken.
+#[derive(Clone)]
+pub struct Link {
+    tx: Option<Sender<i64>>,
+    shared: Arc<Shared>,
+}
+
+impl Link {
+    pub fn send(&self, value: i64) -> Result<(), SendError<i64>> {
+        let mut status = self.shared.status.lock().unwrap();
+        let result = self.tx.as_ref().unwrap().send(value);
+        status.epoch += 1;
+        self.shared.changed.notify_all();
+        result
+    }
+}
+
+impl Drop for Link {
+    // dropping the last sender closes the channel, which waiting machines
+    // need to notice
+    fn drop(&mut self) {
+        let mut status = self.shared.status.lock().unwrap();
+        self.tx.take();
+        status.epoch += 1;
+        self.shared.changed.notify_all();
+    }
+}
+
+// Marks a machine as finished however its thread ends, including by panic.
+struct Exit {
+    id: usize,
+    shared: Arc<Shared>,
+}
+
+impl Drop for Exit {
+    fn drop(&mut self) {
+        let mut status = match self.shared.status.lock() {
+            Ok(status) => status,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        status.alive[self.id] = false;
+        status.epoch += 1;
+        if thread::panicking() {
+            status.fail(Failure::Panicked { id: self.id });
+        }
+        self.shared.changed.notify_all();
+    }
+}
+
+// Runs each machine on its own thread, with input and output bound to
+// channels made by `channel`. `join` waits for them all to halt, stopping
+// the whole network at the first error or once no machine can make
+// progress. Machines are only stopped while waiting for input, so one that
+// loops forever without reading will keep `join` waiting too.
+pub struct Supervisor {
+    shared: Arc<Shared>,
+    handles: Vec<JoinHandle<Vec<i64>>>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Supervisor {
+        Supervisor::new()
+    }
+}
+
+impl Supervisor {
+    pub fn new() -> Supervisor {
+        let status = Status {
+            epoch: 0,
+            alive: Vec::new(),
+            blocked: Vec::new(),
+            shutdown: false,
+            failure: None,
+        };
+        Supervisor {
+            shared: Arc::new(Shared {
+                status: Mutex::new(status),
+                changed: Condvar::new(),
+            }),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn channel(&self) -> (Link, Receiver<i64>) {
+        let (tx, rx) = mpsc::channel();
+        let link = Link {
+            tx: Some(tx),
+            shared: Arc::clone(&self.shared),
+        };
+        (link, rx)
+    }
+
+    // Start a machine, returning its id. Each value it outputs is sent down
+    // `output`, if given; values sent after the receiving machine has
+    // finished are dropped.
+    pub fn spawn(
+        &mut self,
+        program: Vec<i64>,
+        input: Receiver<i64>,
+        output: Option<Link>,
+    ) -> usize {
+        let id = self.handles.len();
+        {
+            let mut status = self.shared.status.lock().unwrap();
+            status.alive.push(true);
+            status.blocked.push(None);
+        }
+
+        let shared = Arc::clone(&self.shared);
+        let handle = thread::spawn(move || {
+            let exit = Exit {
+                id,
+                shared: Arc::clone(&shared),
+            };
+            // bound after `exit` so that it's dropped first even when
+            // unwinding: whoever reads this machine's output has to see the
+            // channel close before it can be the last machine left waiting
+            let output = output;
+            let mut machine = Machine::new(program);
+            let mut values = Vec::new();
+            loop {
+                match machine.run() {
+                    Ok(State::Output(value)) => {
+                        values.push(value);
+                        if let Some(output) = &output {
+                            let _ = output.send(value);
+                        }
+                    }
+                    Ok(State::Halted) => break,
+                    Ok(State::NeedInput) => {
+                        match shared.receive(id, machine.pc(), &input) {
+                            Some(value) => machine.push_input(value),
+                            None => break,
+                        }
+                    }
+                    Err(error) => {
+                        let mut status = shared.status.lock().unwrap();
+                        status.fail(Failure::Machine { id, error });
+                        break;
+                    }
+                }
+            }
+            drop(output);
+            drop(exit);
+            values
+        });
+        self.handles.push(handle);
+        id
+    }
+
+    // Wait for every machine to finish, returning what each one output in
+    // the order they were spawned.
+    pub fn join(self) -> Result<Vec<Vec<i64>>, Failure> {
+        {
+            let mut status = self.shared.status.lock().unwrap();
+            while status.alive.contains(&true) {
+                if status.failure.is_none() && status.deadlocked() {
+                    let waiting = status.live();
+                    status.fail(Failure::Deadlock { waiting });
+                }
+                if status.failure.is_some() {
+                    status.shutdown = true;
+                    self.shared.changed.notify_all();
+                }
+                status = self.shared.changed.wait(status).unwrap();
+            }
+        }
+
+        let outputs: Vec<Vec<i64>> = self
+            .handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_default())
+            .collect();
+
+        let mut status = match self.shared.status.lock() {
+            Ok(status) => status,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match status.failure.take() {
+            Some(failure) => Err(failure),
+            None => Ok(outputs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{ring, CHAIN, FEEDBACK_LOOP};
+
+    // the last value out of a ring of amplifiers
+    fn amplify(program: &[i64], phases: &[i64]) -> Result<i64, Failure> {
+        let mut supervisor = Supervisor::new();
+        let send = |link: &Link, value| link.send(value).unwrap();
+        for (rx, next) in ring(phases, || supervisor.channel(), send) {
+            supervisor.spawn(program.to_vec(), rx, Some(next));
+        }
+
+        let outputs = supervisor.join()?;
+        Ok(*outputs.last().unwrap().last().unwrap())
+    }
+
+    #[test]
+    fn test_chain() {
+        assert_eq!(amplify(CHAIN.program, &CHAIN.phases), Ok(CHAIN.signal));
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        assert_eq!(
+            amplify(FEEDBACK_LOOP.program, &FEEDBACK_LOOP.phases),
+            Ok(FEEDBACK_LOOP.signal)
+        );
+    }
+
+    #[test]
+    fn test_deadlock() {
+        // two machines each waiting to hear from the other first
+        let program = vec![3, 10, 4, 10, 99];
+        let mut supervisor = Supervisor::new();
+        let (a, a_rx) = supervisor.channel();
+        let (b, b_rx) = supervisor.channel();
+        supervisor.spawn(program.clone(), a_rx, Some(b.clone()));
+        supervisor.spawn(program, b_rx, Some(a.clone()));
+        assert_eq!(
+            supervisor.join(),
+            Err(Failure::Deadlock {
+                waiting: vec![0, 1]
+            })
+        );
+        drop((a, b));
+    }
+
+    #[test]
+    fn test_deadlock_after_progress() {
+        // a echoes one value to b, which wants two
+        let mut supervisor = Supervisor::new();
+        let (a, a_rx) = supervisor.channel();
+        let (b, b_rx) = supervisor.channel();
+        a.send(7).unwrap();
+        supervisor.spawn(vec![3, 10, 4, 10, 3, 10, 99], a_rx, Some(b.clone()));
+        supervisor.spawn(vec![3, 10, 3, 11, 99], b_rx, None);
+        assert_eq!(
+            supervisor.join(),
+            Err(Failure::Deadlock {
+                waiting: vec![0, 1]
+            })
+        );
+        drop((a, b));
+    }
+
+    #[test]
+    fn test_error_stops_network() {
+        let mut supervisor = Supervisor::new();
+        let (waiting, waiting_rx) = supervisor.channel();
+        let (_, failing_rx) = supervisor.channel();
+        supervisor.spawn(vec![3, 0, 99], waiting_rx, None);
+        supervisor.spawn(vec![1101, 1, 41, 4, 99], failing_rx, None);
+        assert_eq!(
+            supervisor.join(),
+            Err(Failure::Machine {
+                id: 1,
+                error: Error::UnknownOpCode { pc: 4, opcode: 42 }
+            })
+        );
+        drop(waiting);
+    }
+
+    #[test]
+    fn test_producer_halts() {
+        // the consumer wants a second value from a producer that halts after
+        // one, which closes its input rather than deadlocking it
+        for _ in 0..100 {
+            let mut supervisor = Supervisor::new();
+            let (_, producer_rx) = supervisor.channel();
+            let (link, consumer_rx) = supervisor.channel();
+            supervisor.spawn(vec![104, 5, 99], producer_rx, Some(link));
+            supervisor.spawn(vec![3, 10, 3, 10, 99], consumer_rx, None);
+            assert_eq!(
+                supervisor.join(),
+                Err(Failure::Machine {
+                    id: 1,
+                    error: Error::InputClosed { pc: 2 }
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_input_closed() {
+        let mut supervisor = Supervisor::new();
+        let (link, rx) = supervisor.channel();
+        link.send(1).unwrap();
+        drop(link);
+        supervisor.spawn(vec![3, 10, 3, 10, 99], rx, None);
+        assert_eq!(
+            supervisor.join(),
+            Err(Failure::Machine {
+                id: 0,
+                error: Error::InputClosed { pc: 2 }
+            })
+        );
+    }
+}
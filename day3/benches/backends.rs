@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use day3::wire::WireVec;
+use day3::{backend, Coord, WireSolver, BACKENDS};
+
+// xorshift64, so the wires are the same on every run without pulling in rand
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> Coord {
+        (self.next() % n) as Coord
+    }
+}
+
+// a random walk of `moves` moves, each up to `longest` steps
+fn random_wire(rng: &mut Rng, moves: usize, longest: u64) -> Vec<WireVec> {
+    (0..moves)
+        .map(|_| {
+            let d = rng.below(longest) + 1;
+            match rng.below(4) {
+                0 => WireVec::Up(d),
+                1 => WireVec::Down(d),
+                2 => WireVec::Left(d),
+                _ => WireVec::Right(d),
+            }
+        })
+        .collect()
+}
+
+fn time(
+    name: &str,
+    runs: u32,
+    solver: &dyn WireSolver,
+    wires: &[Vec<WireVec>],
+) -> Duration {
+    let start = Instant::now();
+    let mut result = 0;
+    for _ in 0..runs {
+        result = solver.intersections(wires, 2).len();
+    }
+    let elapsed = start.elapsed() / runs;
+    println!(
+        "{:<24} {:>12?} per run ({} intersections)",
+        name, elapsed, result
+    );
+    elapsed
+}
+
+fn main() {
+    let mut rng = Rng(0x2019_0003);
+    for &(moves, longest) in &[(300, 1000), (3000, 100), (3000, 1000)] {
+        let wires: Vec<Vec<WireVec>> = (0..2)
+            .map(|_| random_wire(&mut rng, moves, longest))
+            .collect();
+        println!("2 wires of {} moves up to {} long", moves, longest);
+        for &name in BACKENDS {
+            time(name, 5, backend(name).unwrap().as_ref(), &wires);
+        }
+        println!();
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use crate::wire::WireVec;
+use crate::{sort_intersections, Coord, Intersection, SortKey, WireSolver};
+
+// Finds intersections by walking every wire one step at a time.
+pub struct Grid;
+
+impl WireSolver for Grid {
+    fn intersections(
+        &self,
+        wires: &[Vec<WireVec>],
+        k: usize,
+    ) -> Vec<Intersection> {
+        assert!(k >= 1, "Points must be shared by at least 1 wire");
+        let mut points: HashMap<(Coord, Coord), Intersection> = HashMap::new();
+        for (i, wire) in wires.iter().enumerate() {
+            for (point, (steps, segment)) in trace(wire) {
+                let p = points.entry(point).or_insert_with(|| Intersection {
+                    point,
+                    steps: vec![None; wires.len()],
+                    segments: vec![None; wires.len()],
+                });
+                p.steps[i] = Some(steps);
+                p.segments[i] = Some(segment);
+            }
+        }
+
+        let mut shared: Vec<Intersection> = points
+            .into_iter()
+            .filter(|(point, p)| {
+                *point != (0, 0) && p.steps.iter().flatten().count() >= k
+            })
+            .map(|(_, p)| p)
+            .collect();
+        sort_intersections(&mut shared, SortKey::Position);
+        shared
+    }
+}
+
+// Walk a wire from the origin, recording the number of steps taken to first
+// reach each point it passes through and the index of the move that got it
+// there. Only visited points are stored, so memory grows with the length of
+// the wire rather than the area it spans.
+fn trace(wire: &[WireVec]) -> HashMap<(Coord, Coord), (Coord, usize)> {
+    let mut visited = HashMap::new();
+    let (mut x, mut y) = (0, 0);
+    let mut steps = 0;
+    for (segment, dir) in wire.iter().enumerate() {
+        let (dx, dy, d) = dir.step();
+        for _ in 0..d {
+            x += dx;
+            y += dy;
+            steps += 1;
+            visited.entry((x, y)).or_insert((steps, segment));
+        }
+    }
+
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::parse_wire;
+
+    #[test]
+    fn test_shared_by_k() {
+        // three wires running through (2, 2)
+        let wires: Vec<Vec<WireVec>> = ["R2,U4", "U2,R4", "R1,U1,R1,U1,R1"]
+            .iter()
+            .map(|s| parse_wire(s))
+            .collect();
+        let shared = Grid.intersections(&wires, 3);
+        assert_eq!(
+            shared,
+            vec![Intersection {
+                point: (2, 2),
+                steps: vec![Some(4), Some(4), Some(4)],
+                segments: vec![Some(1), Some(1), Some(3)],
+            }]
+        );
+        assert_eq!(shared[0].delay(), 12);
+        assert_eq!(Grid.intersections(&wires, 1).len(), 12);
+    }
+
+    #[test]
+    fn test_long_wires() {
+        // a dense grid covering these would need 400 million cells
+        let wires =
+            vec![parse_wire("R20000,U20000"), parse_wire("U20000,R20000")];
+        let found = Grid.intersections(&wires, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].point, (20000, 20000));
+        assert_eq!(found[0].delay(), 80000);
+    }
+
+    #[test]
+    fn test_trace() {
+        let visited = trace(&parse_wire("R2,U1,L1,D1"));
+        assert_eq!(visited.len(), 4);
+        assert_eq!(visited[&(2, 1)], (3, 1));
+        // the wire returns to (1, 0), keeping its first visit
+        assert_eq!(visited[&(1, 0)], (1, 0));
+    }
+}
@@ -0,0 +1,697 @@
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+
+use crate::Coord;
+
+// Whether an interval includes the point at one of its ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bound {
+    Closed,
+    Open,
+}
+
+impl Bound {
+    fn flip(self) -> Bound {
+        match self {
+            Bound::Closed => Bound::Open,
+            Bound::Open => Bound::Closed,
+        }
+    }
+}
+
+// A stretch of the number line from `start` to `end`. Each end is closed or
+// open on its own; those made by `new`, like the stretches wires run along,
+// are closed at both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub start: Coord,
+    pub end: Coord,
+    pub start_bound: Bound,
+    pub end_bound: Bound,
+}
+
+impl Interval {
+    // the closed interval between two points, in either order
+    pub fn new(start: Coord, end: Coord) -> Interval {
+        Interval::closed(start.min(end), start.max(end))
+    }
+
+    pub fn closed(start: Coord, end: Coord) -> Interval {
+        Interval::with_bounds(start, Bound::Closed, end, Bound::Closed)
+    }
+
+    pub fn open(start: Coord, end: Coord) -> Interval {
+        Interval::with_bounds(start, Bound::Open, end, Bound::Open)
+    }
+
+    // closed at the start and open at the end, like `start..end`
+    pub fn half_open(start: Coord, end: Coord) -> Interval {
+        Interval::with_bounds(start, Bound::Closed, end, Bound::Open)
+    }
+
+    pub fn point(p: Coord) -> Interval {
+        Interval::closed(p, p)
+    }
+
+    pub fn with_bounds(
+        start: Coord,
+        start_bound: Bound,
+        end: Coord,
+        end_bound: Bound,
+    ) -> Interval {
+        Interval {
+            start,
+            end,
+            start_bound,
+            end_bound,
+        }
+    }
+
+    // Orders starts so that earlier starts come first, with a closed start
+    // before an open one at the same point as it takes in more.
+    fn start_key(&self) -> (Coord, bool) {
+        (self.start, self.start_bound == Bound::Open)
+    }
+
+    // Orders ends so that later ends come last, with a closed end after an
+    // open one at the same point.
+    fn end_key(&self) -> (Coord, bool) {
+        (self.end, self.end_bound == Bound::Closed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self.start.cmp(&self.end) {
+            Ordering::Less => false,
+            Ordering::Equal => {
+                self.start_bound == Bound::Open || self.end_bound == Bound::Open
+            }
+            Ordering::Greater => true,
+        }
+    }
+
+    pub fn contains_point(&self, pt: Coord) -> bool {
+        let after_start = match self.start_bound {
+            Bound::Closed => self.start <= pt,
+            Bound::Open => self.start < pt,
+        };
+        let before_end = match self.end_bound {
+            Bound::Closed => pt <= self.end,
+            Bound::Open => pt < self.end,
+        };
+        after_start && before_end
+    }
+
+    pub fn contains(&self, rhs: &Interval) -> bool {
+        rhs.is_empty()
+            || (self.start_key() <= rhs.start_key()
+                && rhs.end_key() <= self.end_key())
+    }
+
+    pub fn intersects(&self, rhs: &Interval) -> bool {
+        self.intersection(rhs).is_some()
+    }
+
+    // the interval covered by both, if any
+    pub fn intersection(&self, rhs: &Interval) -> Option<Interval> {
+        let start = if self.start_key() >= rhs.start_key() {
+            self
+        } else {
+            rhs
+        };
+        let end = if self.end_key() <= rhs.end_key() {
+            self
+        } else {
+            rhs
+        };
+        let both = Interval::with_bounds(
+            start.start,
+            start.start_bound,
+            end.end,
+            end.end_bound,
+        );
+        if both.is_empty() {
+            None
+        } else {
+            Some(both)
+        }
+    }
+
+    // the interval covered by either, if there is no gap between them
+    pub fn union(&self, rhs: &Interval) -> Option<Interval> {
+        if self.is_empty() {
+            return Some(*rhs);
+        } else if rhs.is_empty() {
+            return Some(*self);
+        }
+
+        let (first, second) = if self.start_key() <= rhs.start_key() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+        let joined = match second.start.cmp(&first.end) {
+            Ordering::Less => true,
+            Ordering::Equal => {
+                first.end_bound == Bound::Closed
+                    || second.start_bound == Bound::Closed
+            }
+            Ordering::Greater => false,
+        };
+        if !joined {
+            return None;
+        }
+        let last = if first.end_key() >= second.end_key() {
+            first
+        } else {
+            second
+        };
+        Some(Interval::with_bounds(
+            first.start,
+            first.start_bound,
+            last.end,
+            last.end_bound,
+        ))
+    }
+
+    // what is left of this interval without `rhs`: nothing, or the pieces
+    // before and after it
+    pub fn difference(&self, rhs: &Interval) -> Vec<Interval> {
+        if !self.intersects(rhs) {
+            return if self.is_empty() { vec![] } else { vec![*self] };
+        }
+        let before = Interval::with_bounds(
+            self.start,
+            self.start_bound,
+            rhs.start,
+            rhs.start_bound.flip(),
+        );
+        let after = Interval::with_bounds(
+            rhs.end,
+            rhs.end_bound.flip(),
+            self.end,
+            self.end_bound,
+        );
+        vec![before, after]
+            .into_iter()
+            .filter(|piece| !piece.is_empty())
+            .collect()
+    }
+
+    // the whole numbers in the interval
+    pub fn lattice_points(&self) -> RangeInclusive<Coord> {
+        let first = match self.start_bound {
+            Bound::Closed => Some(self.start),
+            Bound::Open => self.start.checked_add(1),
+        };
+        let last = match self.end_bound {
+            Bound::Closed => Some(self.end),
+            Bound::Open => self.end.checked_sub(1),
+        };
+        match (first, last) {
+            (Some(first), Some(last)) => first..=last,
+            // open just past either end of the number line, so there's no
+            // whole number after the start or before the end
+            _ => RangeInclusive::new(1, 0),
+        }
+    }
+}
+
+// Anything covering an interval of the number line, which can be held in a
+// `SegmentTree` or `IntervalTree`.
+pub trait Span {
+    fn span(&self) -> Interval;
+}
+
+impl Span for Interval {
+    fn span(&self) -> Interval {
+        *self
+    }
+}
+
+// an item tagged with its position in some list
+impl<T: Span> Span for (usize, T) {
+    fn span(&self) -> Interval {
+        self.1.span()
+    }
+}
+
+// A set of points on the number line, kept as the fewest intervals that
+// cover it: in order, with no two overlapping or touching.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+        // merge with everything it overlaps or touches
+        let mut merged = interval;
+        let mut kept = Vec::with_capacity(self.intervals.len() + 1);
+        for &other in &self.intervals {
+            match merged.union(&other) {
+                Some(both) => merged = both,
+                None => kept.push(other),
+            }
+        }
+        let at = kept.partition_point(|i| i.start_key() < merged.start_key());
+        kept.insert(at, merged);
+        self.intervals = kept;
+    }
+
+    pub fn remove(&mut self, interval: &Interval) {
+        self.intervals = self
+            .intervals
+            .iter()
+            .flat_map(|i| i.difference(interval))
+            .collect();
+    }
+
+    pub fn contains_point(&self, pt: Coord) -> bool {
+        // the first interval that doesn't end before `pt`
+        let at = self.intervals.partition_point(|i| i.end_key() < (pt, true));
+        self.intervals.get(at).is_some_and(|i| i.contains_point(pt))
+    }
+
+    pub fn union(&self, rhs: &IntervalSet) -> IntervalSet {
+        let mut both = self.clone();
+        for &interval in &rhs.intervals {
+            both.insert(interval);
+        }
+        both
+    }
+
+    pub fn intersection(&self, rhs: &IntervalSet) -> IntervalSet {
+        // walk both in order, moving on from whichever ends first
+        let (mut i, mut j) = (0, 0);
+        let mut both = Vec::new();
+        while i < self.intervals.len() && j < rhs.intervals.len() {
+            let (a, b) = (&self.intervals[i], &rhs.intervals[j]);
+            both.extend(a.intersection(b));
+            if a.end_key() <= b.end_key() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals: both }
+    }
+
+    pub fn difference(&self, rhs: &IntervalSet) -> IntervalSet {
+        let mut left = self.clone();
+        for interval in &rhs.intervals {
+            left.remove(interval);
+        }
+        left
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> IntervalSet {
+        let mut set = IntervalSet::new();
+        for interval in iter {
+            set.insert(interval);
+        }
+        set
+    }
+}
+
+// A centred interval tree: each node holds the items covering its centre,
+// sorted by where they start and by where they end, with the items wholly
+// before and after the centre in its two subtrees. Finding the k items that
+// overlap an interval takes O(log n + k).
+#[derive(Debug)]
+pub struct IntervalTree<T> {
+    items: Vec<T>,
+    root: Option<Box<Centre>>,
+}
+
+#[derive(Debug)]
+struct Centre {
+    // twice the centre, so that it can fall halfway between whole numbers
+    twice: i128,
+    by_start: Vec<usize>,
+    by_end: Vec<usize>,
+    before: Option<Box<Centre>>,
+    after: Option<Box<Centre>>,
+}
+
+// where `interval` lies relative to the point `twice / 2`
+fn compare(interval: &Interval, twice: i128) -> Ordering {
+    let (start, end) = (2 * interval.start as i128, 2 * interval.end as i128);
+    if end < twice || (end == twice && interval.end_bound == Bound::Open) {
+        Ordering::Less
+    } else if twice < start
+        || (twice == start && interval.start_bound == Bound::Open)
+    {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+impl<T: Span> IntervalTree<T> {
+    pub fn new(items: Vec<T>) -> IntervalTree<T> {
+        // empty intervals overlap nothing, so they stay out of the tree
+        let ids = (0..items.len())
+            .filter(|&id| !items[id].span().is_empty())
+            .collect();
+        let root = Self::build(&items, ids);
+        IntervalTree { items, root }
+    }
+
+    fn build(items: &[T], mut ids: Vec<usize>) -> Option<Box<Centre>> {
+        if ids.is_empty() {
+            return None;
+        }
+        // the middle of the median interval, which at least it covers
+        ids.sort_by_key(|&id| items[id].span().start_key());
+        let median = items[ids[ids.len() / 2]].span();
+        let twice = median.start as i128 + median.end as i128;
+
+        let (mut before, mut after, mut here) = (vec![], vec![], vec![]);
+        for id in ids {
+            match compare(&items[id].span(), twice) {
+                Ordering::Less => before.push(id),
+                Ordering::Greater => after.push(id),
+                Ordering::Equal => here.push(id),
+            }
+        }
+        let by_start = here.clone();
+        let mut by_end = here;
+        by_end.sort_by_key(|&id| std::cmp::Reverse(items[id].span().end_key()));
+
+        Some(Box::new(Centre {
+            twice,
+            by_start,
+            by_end,
+            before: Self::build(items, before),
+            after: Self::build(items, after),
+        }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // every item overlapping `interval`
+    pub fn query(&self, interval: &Interval) -> Vec<&T> {
+        let mut found = Vec::new();
+        if !interval.is_empty() {
+            self.visit(self.root.as_deref(), interval, &mut found);
+        }
+        found
+    }
+
+    fn visit<'a>(
+        &'a self,
+        node: Option<&Centre>,
+        interval: &Interval,
+        found: &mut Vec<&'a T>,
+    ) {
+        let centre = match node {
+            Some(centre) => centre,
+            None => return,
+        };
+        let overlapping =
+            |id: &&usize| self.items[**id].span().intersects(interval);
+        match compare(interval, centre.twice) {
+            // everything here reaches past the centre, so only where each
+            // starts or ends matters
+            Ordering::Less => {
+                let ids = centre.by_start.iter().take_while(overlapping);
+                found.extend(ids.map(|&id| &self.items[id]));
+                self.visit(centre.before.as_deref(), interval, found);
+            }
+            Ordering::Greater => {
+                let ids = centre.by_end.iter().take_while(overlapping);
+                found.extend(ids.map(|&id| &self.items[id]));
+                self.visit(centre.after.as_deref(), interval, found);
+            }
+            Ordering::Equal => {
+                found.extend(centre.by_start.iter().map(|&id| &self.items[id]));
+                self.visit(centre.before.as_deref(), interval, found);
+                self.visit(centre.after.as_deref(), interval, found);
+            }
+        }
+    }
+
+    // every item covering `pt`
+    pub fn query_point(&self, pt: Coord) -> Vec<&T> {
+        self.query(&Interval::point(pt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let open = Interval::open(0, 2);
+        assert!(!open.contains_point(0) && open.contains_point(1));
+        assert!(!open.contains_point(2));
+        let half = Interval::half_open(0, 2);
+        assert!(half.contains_point(0) && !half.contains_point(2));
+
+        assert!(Interval::open(3, 3).is_empty());
+        assert!(Interval::half_open(3, 3).is_empty());
+        assert!(!Interval::point(3).is_empty());
+        // not empty on the number line, though no whole number is in it
+        assert!(!Interval::open(3, 4).is_empty());
+        assert_eq!(Interval::open(3, 4).lattice_points().count(), 0);
+        assert_eq!(Interval::half_open(3, 6).lattice_points(), 3..=5);
+
+        assert!(Interval::closed(0, 2).contains(&open));
+        assert!(!open.contains(&Interval::closed(0, 2)));
+        assert!(!open.intersects(&Interval::closed(2, 4)));
+        assert!(Interval::closed(0, 2).intersects(&Interval::closed(2, 4)));
+    }
+
+    #[test]
+    fn test_lattice_extremes() {
+        let (min, max) = (Coord::MIN, Coord::MAX);
+        assert_eq!(Interval::open(max, max).lattice_points().count(), 0);
+        assert_eq!(Interval::open(max, 0).lattice_points().count(), 0);
+        assert_eq!(Interval::open(min, min).lattice_points().count(), 0);
+        assert_eq!(Interval::open(0, min).lattice_points().count(), 0);
+        assert_eq!(Interval::half_open(min, min).lattice_points().count(), 0);
+
+        assert_eq!(
+            Interval::open(min, min + 2).lattice_points(),
+            min + 1..=min + 1
+        );
+        assert_eq!(
+            Interval::open(max - 2, max).lattice_points(),
+            max - 1..=max - 1
+        );
+        assert_eq!(
+            Interval::closed(max - 1, max).lattice_points(),
+            max - 1..=max
+        );
+    }
+
+    #[test]
+    fn test_interval() {
+        let outer = Interval::new(10, 0);
+        let inner = Interval::new(3, 6);
+        assert!(outer.contains(&inner) && !inner.contains(&outer));
+        // containment either way is an intersection
+        assert!(outer.intersects(&inner) && inner.intersects(&outer));
+        assert!(outer.intersects(&Interval::new(10, 12)));
+        assert!(!outer.intersects(&Interval::new(11, 12)));
+
+        assert_eq!(
+            outer.intersection(&inner).map(|i| (i.start, i.end)),
+            Some((3, 6))
+        );
+        let touching = outer.intersection(&Interval::new(10, 12));
+        assert_eq!(touching.map(|i| (i.start, i.end)), Some((10, 10)));
+        assert!(outer.intersection(&Interval::new(-2, -1)).is_none());
+
+        assert!(outer.contains_point(0) && outer.contains_point(10));
+        assert!(!outer.contains_point(11));
+    }
+
+    #[test]
+    fn test_intersection() {
+        let a = Interval::closed(0, 10);
+        assert_eq!(
+            a.intersection(&Interval::open(5, 15)),
+            Some(Interval::with_bounds(5, Bound::Open, 10, Bound::Closed))
+        );
+        assert_eq!(
+            a.intersection(&Interval::closed(3, 6)),
+            Some(Interval::closed(3, 6))
+        );
+        assert_eq!(a.intersection(&Interval::open(10, 12)), None);
+        assert_eq!(
+            a.intersection(&Interval::closed(10, 12)),
+            Some(Interval::point(10))
+        );
+    }
+
+    #[test]
+    fn test_union() {
+        let a = Interval::half_open(0, 5);
+        assert_eq!(
+            a.union(&Interval::closed(5, 8)),
+            Some(Interval::closed(0, 8))
+        );
+        // 5 is in neither
+        assert_eq!(a.union(&Interval::open(5, 8)), None);
+        assert_eq!(
+            a.union(&Interval::closed(1, 2)),
+            Some(Interval::half_open(0, 5))
+        );
+        assert_eq!(a.union(&Interval::open(1, 1)), Some(a));
+    }
+
+    #[test]
+    fn test_difference() {
+        let a = Interval::closed(0, 10);
+        assert_eq!(
+            a.difference(&Interval::closed(3, 6)),
+            vec![
+                Interval::half_open(0, 3),
+                Interval::with_bounds(6, Bound::Open, 10, Bound::Closed),
+            ]
+        );
+        assert_eq!(
+            a.difference(&Interval::open(0, 10)),
+            vec![Interval::point(0), Interval::point(10)]
+        );
+        assert_eq!(a.difference(&Interval::closed(-5, 20)), vec![]);
+        assert_eq!(a.difference(&Interval::closed(11, 20)), vec![a]);
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let mut set: IntervalSet = vec![
+            Interval::closed(5, 8),
+            Interval::closed(0, 2),
+            Interval::half_open(2, 4),
+            Interval::open(20, 30),
+        ]
+        .into_iter()
+        .collect();
+        // [0, 2] and [2, 4) join up
+        assert_eq!(
+            set.iter().copied().collect::<Vec<_>>(),
+            vec![
+                Interval::half_open(0, 4),
+                Interval::closed(5, 8),
+                Interval::open(20, 30),
+            ]
+        );
+        assert!(set.contains_point(3) && !set.contains_point(4));
+        assert!(!set.contains_point(20) && set.contains_point(21));
+
+        set.insert(Interval::closed(4, 5));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.iter().next(), Some(&Interval::closed(0, 8)));
+
+        set.remove(&Interval::closed(1, 2));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains_point(0) && !set.contains_point(2));
+    }
+
+    #[test]
+    fn test_interval_set_operations() {
+        let a: IntervalSet =
+            vec![Interval::closed(0, 10), Interval::closed(20, 30)]
+                .into_iter()
+                .collect();
+        let b: IntervalSet = vec![Interval::open(5, 25)].into_iter().collect();
+
+        let union: Vec<Interval> = a.union(&b).iter().copied().collect();
+        assert_eq!(union, vec![Interval::closed(0, 30)]);
+
+        let both: Vec<Interval> = a.intersection(&b).iter().copied().collect();
+        assert_eq!(
+            both,
+            vec![
+                Interval::with_bounds(5, Bound::Open, 10, Bound::Closed),
+                Interval::half_open(20, 25),
+            ]
+        );
+
+        let left: Vec<Interval> = a.difference(&b).iter().copied().collect();
+        assert_eq!(
+            left,
+            vec![Interval::closed(0, 5), Interval::closed(25, 30),]
+        );
+        assert!(a.difference(&a).is_empty());
+    }
+
+    #[test]
+    fn test_interval_tree() {
+        let items: Vec<(usize, Interval)> = vec![
+            Interval::closed(0, 10),
+            Interval::open(2, 4),
+            Interval::half_open(4, 8),
+            Interval::closed(12, 15),
+            Interval::point(9),
+            Interval::open(6, 6),
+            Interval::closed(-5, 1),
+        ]
+        .into_iter()
+        .enumerate()
+        .collect();
+        let tree = IntervalTree::new(items.clone());
+        assert_eq!(tree.len(), 7);
+
+        let ids = |found: Vec<&(usize, Interval)>| {
+            let mut ids: Vec<usize> =
+                found.iter().map(|&&(id, _)| id).collect();
+            ids.sort_unstable();
+            ids
+        };
+        assert_eq!(ids(tree.query_point(4)), vec![0, 2]);
+        assert_eq!(ids(tree.query_point(8)), vec![0]);
+        assert_eq!(ids(tree.query(&Interval::open(10, 12))), vec![]);
+        assert_eq!(ids(tree.query(&Interval::closed(10, 12))), vec![0, 3]);
+
+        // against checking every item
+        for a in -6..=16 {
+            for b in a..=16 {
+                for &query in &[Interval::closed(a, b), Interval::open(a, b)] {
+                    let expected: Vec<usize> = items
+                        .iter()
+                        .filter(|(_, i)| i.intersects(&query))
+                        .map(|&(id, _)| id)
+                        .collect();
+                    assert_eq!(
+                        ids(tree.query(&query)),
+                        expected,
+                        "{:?}",
+                        query
+                    );
+                }
+            }
+        }
+    }
+}
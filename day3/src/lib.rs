@@ -0,0 +1,455 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+
+pub mod grid;
+pub mod interval;
+pub mod loops;
+pub mod metric;
+pub mod segment;
+pub mod segment_tree;
+pub mod space;
+pub mod svg;
+pub mod sweep;
+pub mod wire;
+
+use metric::{closest, Metric};
+use wire::{parse_wire, WireVec};
+
+// Coordinates, lengths and step counts, wide enough for wires that run
+// further than 2^31 from the origin.
+pub type Coord = i64;
+
+pub fn read_input(filename: Option<&str>) -> impl Iterator<Item = String> {
+    let filename: &str = filename.unwrap_or("input.txt");
+    let file = File::open(filename);
+
+    if let Ok(file) = file {
+        let reader = io::BufReader::new(file);
+        reader.lines().map(|l| l.unwrap())
+    } else {
+        panic!("Could not open file: {}", filename)
+    }
+}
+
+pub fn parse_wires(input: impl Iterator<Item = String>) -> Vec<Vec<WireVec>> {
+    input
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_wire(&line))
+        .collect()
+}
+
+// A point crossed by several wires. For each wire, `steps` holds the steps it
+// takes to first reach the point and `segments` the index of the `WireVec`
+// it was following at the time, both None for wires that never get there.
+#[derive(Debug, PartialEq)]
+pub struct Intersection<P = (Coord, Coord)> {
+    pub point: P,
+    pub steps: Vec<Option<Coord>>,
+    pub segments: Vec<Option<usize>>,
+}
+
+impl Intersection {
+    // Manhattan distance from the origin
+    pub fn distance(&self) -> Coord {
+        checked_add(self.point.0.abs(), self.point.1.abs())
+    }
+}
+
+impl<P> Intersection<P> {
+    // combined steps of every wire that reaches this point
+    pub fn delay(&self) -> Coord {
+        self.steps
+            .iter()
+            .flatten()
+            .fold(0, |total, &s| checked_add(total, s))
+    }
+}
+
+// `a + b` for positions and step counts, panicking rather than wrapping
+// around on a wire too long to measure.
+pub(crate) fn checked_add(a: Coord, b: Coord) -> Coord {
+    match a.checked_add(b) {
+        Some(sum) => sum,
+        None => panic!("Wire too long: {} + {} overflows", a, b),
+    }
+}
+
+// A way of finding where wires cross.
+pub trait WireSolver {
+    // Every point other than the origin crossed by at least `k` of `wires`,
+    // ordered by position. `k` must be at least 1.
+    fn intersections(
+        &self,
+        wires: &[Vec<WireVec>],
+        k: usize,
+    ) -> Vec<Intersection>;
+}
+
+// Only the grid and segments backends accept diagonal moves.
+pub const BACKENDS: &[&str] = &["grid", "segment-tree", "sweep", "segments"];
+
+pub fn backend(name: &str) -> Option<Box<dyn WireSolver>> {
+    match name {
+        "grid" => Some(Box::new(grid::Grid)),
+        "segment-tree" => Some(Box::new(segment_tree::SegmentTreeSolver)),
+        "sweep" => Some(Box::new(sweep::Sweep)),
+        "segments" => Some(Box::new(segment::Segments)),
+        _ => None,
+    }
+}
+
+// where two wires cross, with the steps each takes to get there along the
+// crossing lines and the indices of those lines
+pub(crate) type Crossing<P = (Coord, Coord)> =
+    (P, (Coord, usize), (Coord, usize));
+
+// Gather the points other than the origin where any two of `wires` meet,
+// splitting each wire into lines with `to_lines` and finding where two of
+// them meet with `crossings`, keeping those shared by at least `k` of them.
+// With `k` of 1 each wire is met with itself instead, as a line shares every
+// point with itself.
+pub(crate) fn pairwise<W, L, P: Copy + Default + Ord>(
+    wires: &[Vec<W>],
+    k: usize,
+    to_lines: fn(&[W]) -> Vec<L>,
+    crossings: fn(&[L], &[L]) -> Vec<Crossing<P>>,
+) -> Vec<Intersection<P>> {
+    assert!(k >= 1, "Points must be shared by at least 1 wire");
+
+    let lines: Vec<Vec<L>> = wires.iter().map(|wire| to_lines(wire)).collect();
+    let mut crossed: BTreeMap<P, Intersection<P>> = BTreeMap::new();
+    let mut visit = |point, wire: usize, (steps, segment): (Coord, usize)| {
+        let p = crossed.entry(point).or_insert_with(|| Intersection {
+            point,
+            steps: vec![None; wires.len()],
+            segments: vec![None; wires.len()],
+        });
+        // keep the first visit when a wire crosses a point twice, and the
+        // earlier line when it reaches the point at a corner
+        match p.steps[wire].zip(p.segments[wire]) {
+            Some(seen) if seen <= (steps, segment) => {}
+            _ => {
+                p.steps[wire] = Some(steps);
+                p.segments[wire] = Some(segment);
+            }
+        }
+    };
+    for i in 0..lines.len() {
+        let others = if k == 1 { i..i + 1 } else { i + 1..lines.len() };
+        for j in others {
+            for (point, a, b) in crossings(&lines[i], &lines[j]) {
+                if point != P::default() {
+                    visit(point, i, a);
+                    visit(point, j, b);
+                }
+            }
+        }
+    }
+
+    crossed
+        .into_values()
+        .filter(|p| p.steps.iter().flatten().count() >= k)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Position,
+    Distance,
+    Delay,
+    // steps or segment index along one wire, with points the wire never
+    // reaches last
+    Steps(usize),
+    Segment(usize),
+}
+
+impl SortKey {
+    // position, distance, delay, steps:N or segment:N
+    pub fn new(input: &str) -> Result<SortKey, String> {
+        let mut parts = input.splitn(2, ':');
+        let wire = |n: Option<&str>| match n.map(str::parse) {
+            Some(Ok(n)) => Ok(n),
+            _ => Err(format!("Sort key needs a wire index: {}", input)),
+        };
+        match parts.next() {
+            Some("position") => Ok(SortKey::Position),
+            Some("distance") => Ok(SortKey::Distance),
+            Some("delay") => Ok(SortKey::Delay),
+            Some("steps") => Ok(SortKey::Steps(wire(parts.next())?)),
+            Some("segment") => Ok(SortKey::Segment(wire(parts.next())?)),
+            _ => Err(format!("Unknown sort key: {}", input)),
+        }
+    }
+}
+
+// Sort by `key`, breaking ties by position.
+pub fn sort_intersections(intersections: &mut [Intersection], key: SortKey) {
+    let last = |value: Option<Coord>| value.unwrap_or(Coord::MAX);
+    intersections.sort_by_key(|p| {
+        let primary = match key {
+            SortKey::Position => 0,
+            SortKey::Distance => p.distance(),
+            SortKey::Delay => p.delay(),
+            SortKey::Steps(i) => last(p.steps[i]),
+            SortKey::Segment(i) => last(p.segments[i].map(|s| s as Coord)),
+        };
+        (primary, p.point)
+    });
+}
+
+// Returns the distance to the closest intersection and the fewest combined
+// steps to reach one.
+pub fn challenge(
+    solver: &dyn WireSolver,
+    input: impl Iterator<Item = String>,
+) -> Option<(Coord, Coord)> {
+    let wires = parse_wires(input);
+    let found = solver.intersections(&wires, 2);
+    let nearest = closest(&found, &Metric::Manhattan, (0, 0), 1).first()?.0;
+    let fastest = closest(&found, &Metric::Delay, (0, 0), 1).first()?.0;
+    Some((nearest, fastest))
+}
+
+// Examples every backend must agree on.
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+
+    macro_rules! str_vec {
+        ( $($s:expr),* $(,)? ) => ( vec![$($s.to_string()),*] )
+    }
+
+    fn solvers() -> Vec<(&'static str, Box<dyn WireSolver>)> {
+        BACKENDS
+            .iter()
+            .map(|&name| (name, backend(name).unwrap()))
+            .collect()
+    }
+
+    fn check(input: Vec<String>, expected: Option<(Coord, Coord)>) {
+        for (name, solver) in solvers() {
+            let result = challenge(solver.as_ref(), input.clone().into_iter());
+            assert_eq!(result, expected, "backend {}", name);
+        }
+    }
+
+    #[test]
+    fn test1() {
+        let input = str_vec![
+            "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+            "U62,R66,U55,R34,D71,R55,D58,R83",
+        ];
+        check(input, Some((159, 610)));
+    }
+
+    #[test]
+    fn test2() {
+        check(str_vec!["R8,U5,L5,D3", "U7,R6,D4,L4"], Some((6, 30)));
+    }
+
+    #[test]
+    fn test3() {
+        let input = str_vec![
+            "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+            "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+        ];
+        check(input, Some((135, 410)));
+    }
+
+    #[test]
+    fn test_input() {
+        let input = include_str!("../input.txt").lines().map(String::from);
+        check(input.collect(), Some((248, 28580)));
+    }
+
+    #[test]
+    fn test_no_intersections() {
+        check(str_vec!["R8,U5,L5,D3"], None);
+        check(str_vec!["R3", "L1,U1,R6,D2"], None);
+    }
+
+    #[test]
+    fn test_many_wires() {
+        let wires =
+            parse_wires(str_vec!["U5,R10", "R3,U10", "L1,U8,R5"].into_iter());
+        for (name, solver) in solvers() {
+            assert_eq!(
+                solver.intersections(&wires, 2),
+                vec![
+                    Intersection {
+                        point: (3, 5),
+                        steps: vec![Some(8), Some(8), None],
+                        segments: vec![Some(1), Some(1), None],
+                    },
+                    Intersection {
+                        point: (3, 8),
+                        steps: vec![None, Some(11), Some(13)],
+                        segments: vec![None, Some(1), Some(2)],
+                    },
+                ],
+                "backend {}",
+                name
+            );
+            assert!(solver.intersections(&wires, 3).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_single_wire() {
+        // every point either wire passes through, with the crossing at (2, 2)
+        // and the wire crossing itself at (1, 1)
+        let input = str_vec!["R2,U4", "U1,R3,U1,L2,D1"];
+        let wires = parse_wires(input.into_iter());
+        let expected = grid::Grid.intersections(&wires, 1);
+        assert_eq!(expected.len(), 11);
+        for (name, solver) in solvers() {
+            let found = solver.intersections(&wires, 1);
+            assert_eq!(found, expected, "backend {}", name);
+        }
+    }
+
+    #[test]
+    fn test_no_wires_required() {
+        let wires = parse_wires(str_vec!["R2", "U2"].into_iter());
+        for (name, solver) in solvers() {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                solver.intersections(&wires, 0);
+            }));
+            assert!(result.is_err(), "backend {}", name);
+        }
+    }
+
+    fn shared(input: &[&str]) -> Vec<(&'static str, Vec<Intersection>)> {
+        let wires = parse_wires(input.iter().map(|s| s.to_string()));
+        solvers()
+            .into_iter()
+            .map(|(name, solver)| (name, solver.intersections(&wires, 2)))
+            .collect()
+    }
+
+    #[test]
+    fn test_partial_overlap() {
+        // wire 2 comes down onto wire 1 at (4, 0) and follows it for 3 steps
+        for (name, found) in shared(&["R10", "U2,R4,D2,R3"]) {
+            let expected: Vec<Intersection> = (4..=7)
+                .map(|x| Intersection {
+                    point: (x, 0),
+                    steps: vec![Some(x), Some(x + 4)],
+                    segments: vec![Some(0), Some(if x == 4 { 2 } else { 3 })],
+                })
+                .collect();
+            assert_eq!(found, expected, "backend {}", name);
+        }
+    }
+
+    #[test]
+    fn test_full_overlap() {
+        for (name, found) in shared(&["R3,U3", "R2"]) {
+            let points: Vec<(Coord, Coord)> =
+                found.iter().map(|p| p.point).collect();
+            assert_eq!(points, vec![(1, 0), (2, 0)], "backend {}", name);
+        }
+        // the same wire backwards, sharing every point but the origin
+        for (name, found) in shared(&["R5", "R5,L5"]) {
+            assert_eq!(found.len(), 5, "backend {}", name);
+            assert_eq!(found[0].steps, vec![Some(1), Some(1)]);
+            assert_eq!(found[4].steps, vec![Some(5), Some(5)]);
+        }
+    }
+
+    #[test]
+    fn test_touching() {
+        // wire 2 meets the end of wire 1 from above, then carries on from it
+        for (name, found) in shared(&["R5", "U1,R5,D1,R2"]) {
+            assert_eq!(
+                found,
+                vec![Intersection {
+                    point: (5, 0),
+                    steps: vec![Some(5), Some(7)],
+                    segments: vec![Some(0), Some(2)],
+                }],
+                "backend {}",
+                name
+            );
+        }
+        // the wires only meet at a corner, where both of them turn
+        for (name, found) in shared(&["R3,U3", "U3,R3"]) {
+            let points: Vec<(Coord, Coord)> =
+                found.iter().map(|p| p.point).collect();
+            assert_eq!(points, vec![(3, 3)], "backend {}", name);
+            assert_eq!(found[0].segments, vec![Some(1), Some(1)]);
+        }
+    }
+
+    #[test]
+    fn test_beyond_i32() {
+        // too far to walk, so for every backend but the grid
+        let cases = [
+            (
+                ["R3000000000,U3000000000", "U3000000000,R3000000000"],
+                (3_000_000_000, 3_000_000_000),
+                12_000_000_000,
+            ),
+            (
+                ["R5000000000,U10", "U5,R6000000000"],
+                (5_000_000_000, 5),
+                10_000_000_010,
+            ),
+        ];
+        for (input, point, delay) in cases.iter() {
+            let wires = parse_wires(input.iter().map(|s| s.to_string()));
+            let far = solvers().into_iter().filter(|(name, _)| *name != "grid");
+            for (name, solver) in far {
+                let found = solver.intersections(&wires, 2);
+                assert_eq!(found.len(), 1, "backend {}", name);
+                assert_eq!(found[0].point, *point, "backend {}", name);
+                assert_eq!(found[0].delay(), *delay, "backend {}", name);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Wire too long")]
+    fn test_overflow() {
+        let wires =
+            parse_wires(str_vec!["R9223372036854775807,L1", "U1"].into_iter());
+        backend("sweep").unwrap().intersections(&wires, 2);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let input = str_vec![
+            "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+            "U62,R66,U55,R34,D71,R55,D58,R83",
+        ];
+        let wires = parse_wires(input.into_iter());
+        for (name, solver) in solvers() {
+            let mut found = solver.intersections(&wires, 2);
+            assert_eq!(found.len(), 4, "backend {}", name);
+
+            sort_intersections(&mut found, SortKey::Distance);
+            assert_eq!(found[0].point, (155, 4));
+            assert_eq!(found[0].distance(), 159);
+
+            sort_intersections(&mut found, SortKey::Delay);
+            assert_eq!(found[0].point, (158, -12));
+            assert_eq!(found[0].delay(), 610);
+            assert_eq!(found[0].steps, vec![Some(206), Some(404)]);
+            assert_eq!(found[0].segments, vec![Some(3), Some(7)]);
+
+            sort_intersections(&mut found, SortKey::new("steps:1").unwrap());
+            let steps: Vec<Coord> =
+                found.iter().map(|p| p.steps[1].unwrap()).collect();
+            assert!(steps.windows(2).all(|w| w[0] <= w[1]));
+
+            sort_intersections(&mut found, SortKey::new("segment:0").unwrap());
+            assert!(found
+                .windows(2)
+                .all(|w| w[0].segments[0] <= w[1].segments[0]));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+
+use crate::segment::wirevecs_to_segments;
+use crate::segment_tree::{rows, wirevecs_to_lines};
+use crate::sweep::sweep;
+use crate::wire::WireVec;
+use crate::Coord;
+
+// A point a wire passes through more than once. `visits` holds the steps
+// taken to reach it each time, in order, along with the index of the
+// `WireVec` being followed.
+#[derive(Debug, PartialEq)]
+pub struct Revisit {
+    pub point: (Coord, Coord),
+    pub visits: Vec<(Coord, usize)>,
+}
+
+impl Revisit {
+    // the steps that count towards signal delay
+    pub fn first(&self) -> Coord {
+        self.visits[0].0
+    }
+}
+
+// A closed stretch of wire that leaves `point` after `start` steps and first
+// comes back to it after `end`.
+#[derive(Debug, PartialEq)]
+pub struct Loop {
+    pub point: (Coord, Coord),
+    pub start: Coord,
+    pub end: Coord,
+}
+
+impl Loop {
+    pub fn length(&self) -> Coord {
+        self.end - self.start
+    }
+}
+
+type Visits = BTreeMap<(Coord, Coord), Vec<(Coord, usize)>>;
+
+// Every point `wire` passes through more than once, ordered by position. The
+// origin counts as visited at step 0, and turning a corner is a single visit.
+pub fn revisits(wire: &[WireVec]) -> Vec<Revisit> {
+    let visits = if wire.iter().any(WireVec::is_diagonal) {
+        segment_visits(wire)
+    } else {
+        line_visits(wire)
+    };
+
+    visits
+        .into_iter()
+        .filter_map(|(point, mut visits)| {
+            // the end of one line and the start of the next are the same
+            // visit, which belongs to the earlier line
+            visits.sort_unstable();
+            visits.dedup_by_key(|&mut (steps, _)| steps);
+            if visits.len() > 1 {
+                Some(Revisit { point, visits })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Where the lines of an axis-aligned wire meet each other, found with a
+// sweep and by comparing the lines along each row or column.
+fn line_visits(wire: &[WireVec]) -> Visits {
+    let lines = wirevecs_to_lines(wire);
+    let mut visits = Visits::new();
+    let mut visit = |point, i: usize| {
+        let steps = lines[i].steps_to(point);
+        visits.entry(point).or_default().push((steps, i));
+    };
+
+    for (i, j) in sweep(&lines, &lines) {
+        let point = lines[i].crossing_point(&lines[j]);
+        visit(point, i);
+        visit(point, j);
+    }
+    for row in rows(&lines).values() {
+        for (n, &i) in row.iter().enumerate() {
+            for &j in &row[n + 1..] {
+                for point in lines[i].shared_points(&lines[j]) {
+                    visit(point, i);
+                    visit(point, j);
+                }
+            }
+        }
+    }
+    visits
+}
+
+// Where the segments of a wire that may move diagonally meet each other,
+// comparing every pair.
+fn segment_visits(wire: &[WireVec]) -> Visits {
+    let segments = wirevecs_to_segments(wire);
+    let mut visits = Visits::new();
+    for (i, a) in segments.iter().enumerate() {
+        for (j, b) in segments.iter().enumerate().skip(i + 1) {
+            for point in a.shared_points(b) {
+                visits
+                    .entry(point)
+                    .or_default()
+                    .push((a.steps_to(point), i));
+                visits
+                    .entry(point)
+                    .or_default()
+                    .push((b.steps_to(point), j));
+            }
+        }
+    }
+    visits
+}
+
+// Every loop in `wire`, in the order the wire closes them. A point visited n
+// times closes n - 1 loops.
+pub fn loops(wire: &[WireVec]) -> Vec<Loop> {
+    let mut loops: Vec<Loop> = revisits(wire)
+        .into_iter()
+        .flat_map(|r| {
+            let point = r.point;
+            r.visits
+                .windows(2)
+                .map(|w| Loop {
+                    point,
+                    start: w[0].0,
+                    end: w[1].0,
+                })
+                .collect::<Vec<Loop>>()
+        })
+        .collect();
+    loops.sort_by_key(|l| (l.end, l.start));
+    loops
+}
+
+// whether `wire` never crosses or runs back over itself
+pub fn is_self_avoiding(wire: &[WireVec]) -> bool {
+    revisits(wire).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::parse_wire;
+
+    #[test]
+    fn test_revisits() {
+        // (1, 0) is crossed going right and again coming down
+        let wire = parse_wire("R2,U1,L1,D2");
+        assert_eq!(
+            revisits(&wire),
+            vec![Revisit {
+                point: (1, 0),
+                visits: vec![(1, 0), (5, 3)],
+            }]
+        );
+        assert_eq!(
+            loops(&wire),
+            vec![Loop {
+                point: (1, 0),
+                start: 1,
+                end: 5,
+            }]
+        );
+        assert_eq!(loops(&wire)[0].length(), 4);
+    }
+
+    #[test]
+    fn test_retraced() {
+        // every point but the far end is visited on the way out and back
+        let found = revisits(&parse_wire("R5,L5"));
+        let points: Vec<(Coord, Coord)> =
+            found.iter().map(|r| r.point).collect();
+        assert_eq!(points, vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        assert_eq!(found[0].visits, vec![(0, 0), (10, 1)]);
+        assert_eq!(found[4].visits, vec![(4, 0), (6, 1)]);
+    }
+
+    #[test]
+    fn test_repeated_loop() {
+        // around the unit square twice
+        let wire = parse_wire("R1,U1,L1,D1,R1,U1,L1,D1");
+        let found = revisits(&wire);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[0].point, (0, 0));
+        assert_eq!(found[0].visits, vec![(0, 0), (4, 3), (8, 7)]);
+        assert_eq!(found[2].point, (1, 0));
+        assert_eq!(found[2].first(), 1);
+
+        let lengths: Vec<Coord> =
+            loops(&wire).iter().map(|l| l.length()).collect();
+        assert_eq!(lengths, vec![4; 5]);
+        assert_eq!(loops(&wire)[0].point, (0, 0));
+    }
+
+    #[test]
+    fn test_diagonal() {
+        // up the diagonal, then down, left and back up through its middle
+        let wire = parse_wire("NE2,D2,L1,U2");
+        assert_eq!(
+            revisits(&wire),
+            vec![Revisit {
+                point: (1, 1),
+                visits: vec![(1, 0), (6, 3)],
+            }]
+        );
+
+        // back down the same diagonal
+        let found = revisits(&parse_wire("NE2,SW2"));
+        let points: Vec<(Coord, Coord)> =
+            found.iter().map(|r| r.point).collect();
+        assert_eq!(points, vec![(0, 0), (1, 1)]);
+        assert_eq!(found[0].visits, vec![(0, 0), (4, 1)]);
+    }
+
+    #[test]
+    fn test_diagonal_loops() {
+        // a triangle closed back at the origin, then a diagonal that only
+        // meets the first one between lattice points
+        let wire = parse_wire("NE2,D2,L2,U1,SE2");
+        assert_eq!(
+            loops(&wire),
+            vec![
+                Loop {
+                    point: (0, 0),
+                    start: 0,
+                    end: 6,
+                },
+                Loop {
+                    point: (1, 0),
+                    start: 5,
+                    end: 8,
+                },
+            ]
+        );
+        assert!(!is_self_avoiding(&wire));
+        assert!(is_self_avoiding(&parse_wire("NE3,SE3,R1,NW1")));
+    }
+
+    #[test]
+    fn test_self_avoiding() {
+        assert!(is_self_avoiding(&parse_wire("R8,U5,L5,D3")));
+        assert!(!is_self_avoiding(&parse_wire("R2,U1,L1,D2")));
+        assert!(loops(&parse_wire("U7,R6,D4,L4")).is_empty());
+    }
+}
@@ -1,183 +1,231 @@
 use std::env;
-use std::fs::File;
-use std::io;
-use std::io::prelude::*;
-
-fn read_input(filename: Option<&str>) -> impl Iterator<Item = String> {
-    let filename: &str = filename.unwrap_or("input.txt");
-    let file = File::open(filename);
-
-    if let Ok(file) = file {
-        let reader = io::BufReader::new(file);
-        reader.lines().map(|l| l.unwrap())
-    } else {
-        panic!("Could not open file: {}", filename)
-    }
+use std::fs;
+use std::process;
+
+use day3::loops::loops;
+use day3::metric::{closest, Metric};
+use day3::svg::render;
+use day3::{
+    backend, challenge, parse_wires, read_input, sort_intersections, Coord,
+    SortKey, BACKENDS,
+};
+
+const EXIT_USAGE: i32 = 64;
+
+const USAGE: &str = "\
+usage: day3 [input] [options]
+
+reads one wire per line from input, input.txt unless given, and prints the
+distance to the closest intersection and the fewest combined steps to one
+
+options:
+  --backend NAME   solver: grid, segment-tree, sweep or segments (default grid)
+  --sort KEY       print every intersection ordered by KEY instead: position,
+                   distance, delay, steps:N or segment:N
+  --closest K      print the K intersections closest to the origin instead
+  --metric NAME    how --closest and --svg measure distance: manhattan,
+                   chebyshev, euclidean or delay (default manhattan)
+  --origin X,Y     point --closest and --svg measure from (default 0,0)
+  --svg FILE       also draw the wires and their intersections to FILE,
+                   picking out the closest intersection
+  --loops          print the loops in each wire instead
+
+exit status: 0 success, 64 bad usage";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    filename: Option<String>,
+    backend: String,
+    sort: Option<SortKey>,
+    loops: bool,
+    closest: Option<usize>,
+    metric: String,
+    origin: (Coord, Coord),
+    svg: Option<String>,
 }
 
 fn main() {
-    let filename = env::args().nth(1);
-    let input = read_input(filename.as_ref().map(String::as_str));
-    println!("{:?}", challenge(input));
-}
-
-fn find_array_dimensions(wire: &Vec<WireVec>) -> (i32, i32, i32, i32) {
-    let (mut min_x, mut max_x) = (0, 0);
-    let (mut min_y, mut max_y) = (0, 0);
-    let (mut x, mut y) = (0, 0);
-    for dir in wire {
-        match dir {
-            WireVec::Up(i) => y += i,
-            WireVec::Down(i) => y -= i,
-            WireVec::Left(i) => x -= i,
-            WireVec::Right(i) => x += i,
-        }
-        if y < min_y {
-            min_y = y;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
         }
-        if y > max_y {
-            max_y = y;
-        }
-        if x < min_x {
-            min_x = x;
+    };
+    // parse_args has already checked the names
+    let solver = backend(&options.backend).unwrap();
+    let metric = Metric::new(&options.metric).unwrap();
+    let origin = options.origin;
+
+    let input = read_input(options.filename.as_deref());
+    let input: Vec<String> = input.collect();
+    if let Some(path) = options.svg {
+        let wires = parse_wires(input.iter().cloned());
+        let found = solver.intersections(&wires, 2);
+        let svg = render(&wires, &found, &metric, origin);
+        fs::write(&path, svg).expect("Could not write SVG file");
+    }
+    let input = input.into_iter();
+    if options.loops {
+        for (i, wire) in parse_wires(input).iter().enumerate() {
+            for found in loops(wire) {
+                println!("wire {}: {:?}", i, found);
+            }
         }
-        if x > max_x {
-            max_x = x;
+        return;
+    }
+    if let Some(k) = options.closest {
+        let wires = parse_wires(input);
+        let found = solver.intersections(&wires, 2);
+        for (distance, intersection) in closest(&found, &metric, origin, k) {
+            println!("{} {:?}", distance, intersection);
         }
+        return;
     }
-
-    (min_x, max_x, min_y, max_y)
-}
-
-fn challenge(mut input: impl Iterator<Item = String>) -> Option<i32> {
-    let str1 = input.next().unwrap();
-    let str2 = input.next().unwrap();
-
-    let wire1 = str1.split(',').map(WireVec::new).collect();
-    let wire2: Vec<WireVec> = str2.split(',').map(WireVec::new).collect();
-
-    // create 2d array
-    let (min_x, max_x, min_y, max_y) = find_array_dimensions(&wire1);
-    let width = (max_x - min_x) as usize + 1;
-    let height = (max_y - min_y) as usize + 1;
-    let mut arr = vec![(false, 0); width * height];
-
-    // plot wire 1
-    // start at (0, 0) in array coordinates
-    let (mut x, mut y) = (-min_x, -min_y);
-    let mut steps = 0;
-    for dir in wire1 {
-        let (move_x, move_y) = match dir {
-            WireVec::Up(d) => (0, d),
-            WireVec::Down(d) => (0, -d),
-            WireVec::Left(d) => (-d, 0),
-            WireVec::Right(d) => (d, 0),
-        };
-
-        let (end_x, end_y) = (x + move_x, y + move_y);
-        let (dx, dy) = (move_x.signum(), move_y.signum());
-        loop {
-            arr[width * y as usize + x as usize] = (true, steps);
-            if x == end_x && y == end_y {
-                break;
+    match options.sort {
+        Some(key) => {
+            let wires = parse_wires(input);
+            let mut found = solver.intersections(&wires, 2);
+            sort_intersections(&mut found, key);
+            for intersection in found {
+                println!("{:?}", intersection);
             }
-            x += dx;
-            y += dy;
-            steps += 1;
         }
+        None => println!("{:?}", challenge(solver.as_ref(), input)),
     }
+}
 
-    // plot wire 2
-    // start at (0, 0) in array coordinates
-    let (mut x, mut y) = (-min_x, -min_y);
-    let mut steps = 0;
-    let mut steps_min = None;
-    for dir in wire2 {
-        let (move_x, move_y) = match dir {
-            WireVec::Up(d) => (0, d),
-            WireVec::Down(d) => (0, -d),
-            WireVec::Left(d) => (-d, 0),
-            WireVec::Right(d) => (d, 0),
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        filename: None,
+        backend: "grid".to_string(),
+        sort: None,
+        loops: false,
+        closest: None,
+        metric: "manhattan".to_string(),
+        origin: (0, 0),
+        svg: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
         };
-
-        let (dx, dy) = (move_x.signum(), move_y.signum());
-        let (end_x, end_y) = (x + move_x, y + move_y);
-        loop {
-            // check for intersections with wire 1
-            let i = width as i32 * y + x;
-            if let Some((set, wire1_steps)) = arr.get(i as usize) {
-                if *set {
-                    let dist = steps + wire1_steps;
-                    if dist != 0 {
-                        steps_min = match steps_min {
-                            Some(cur_min) if dist < cur_min => Some(dist),
-                            None => Some(dist),
-                            _ => steps_min,
-                        }
+        match arg.as_str() {
+            "--backend" => {
+                let name = value()?;
+                if backend(name).is_none() {
+                    return Err(format!(
+                        "Unknown backend: {} (expected one of {})",
+                        name,
+                        BACKENDS.join(", ")
+                    ));
+                }
+                options.backend = name.to_string();
+            }
+            "--sort" => options.sort = Some(SortKey::new(value()?)?),
+            "--loops" => options.loops = true,
+            "--closest" => options.closest = Some(parse(value()?, arg)?),
+            "--metric" => {
+                let name = value()?;
+                Metric::new(name)?;
+                options.metric = name.to_string();
+            }
+            "--origin" => {
+                let point = value()?;
+                let mut coords = point.split(',');
+                match (coords.next(), coords.next(), coords.next()) {
+                    (Some(x), Some(y), None) => {
+                        options.origin = (parse(x, arg)?, parse(y, arg)?)
+                    }
+                    _ => {
+                        return Err(format!("Origin should be X,Y: {}", point))
                     }
                 }
             }
-            if x == end_x && y == end_y {
-                break;
+            "--svg" => options.svg = Some(value()?.to_string()),
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option: {}", flag))
             }
-            x += dx;
-            y += dy;
-            steps += 1;
+            path if options.filename.is_none() => {
+                options.filename = Some(path.to_string())
+            }
+            extra => return Err(format!("Unexpected argument: {}", extra)),
         }
     }
-
-    steps_min
-}
-
-#[derive(Debug)]
-enum WireVec {
-    Up(i32),
-    Down(i32),
-    Left(i32),
-    Right(i32),
+    Ok(options)
 }
 
-impl WireVec {
-    fn new(input: &str) -> WireVec {
-        match input.chars().nth(0) {
-            Some('U') => WireVec::Up(input[1..].parse().unwrap()),
-            Some('D') => WireVec::Down(input[1..].parse().unwrap()),
-            Some('L') => WireVec::Left(input[1..].parse().unwrap()),
-            Some('R') => WireVec::Right(input[1..].parse().unwrap()),
-            dir => panic!("Unknown direction: {:?}", dir),
-        }
-    }
+fn parse<T: std::str::FromStr>(s: &str, option: &str) -> Result<T, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("Bad value for {}: {}", option, s))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    macro_rules! str_vec {
-        ( $($s:expr),* $(,)? ) => ( vec![$($s.to_string()),*] )
+    fn try_parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        parse_args(&args)
     }
 
     #[test]
-    fn test1() {
-        let input = str_vec![
-            "R75,D30,R83,U83,L12,D49,R71,U7,L72",
-            "U62,R66,U55,R34,D71,R55,D58,R83",
+    fn test_options() {
+        let args = [
+            "wires.txt",
+            "--backend",
+            "sweep",
+            "--sort",
+            "steps:1",
+            "--closest",
+            "3",
+            "--metric",
+            "chebyshev",
+            "--origin",
+            "-4, 7",
+            "--svg",
+            "out.svg",
+            "--loops",
         ];
-        assert_eq!(challenge(input.into_iter()), Some(610));
+        let options = try_parse(&args).unwrap();
+        assert_eq!(options.filename.as_deref(), Some("wires.txt"));
+        assert_eq!(options.backend, "sweep");
+        assert_eq!(options.sort, Some(SortKey::Steps(1)));
+        assert_eq!(options.closest, Some(3));
+        assert_eq!(options.metric, "chebyshev");
+        assert_eq!(options.origin, (-4, 7));
+        assert_eq!(options.svg.as_deref(), Some("out.svg"));
+        assert!(options.loops);
+
+        let defaults = try_parse(&[]).unwrap();
+        assert_eq!(defaults.filename, None);
+        assert_eq!(defaults.backend, "grid");
+        assert_eq!(defaults.metric, "manhattan");
+        assert_eq!(defaults.origin, (0, 0));
     }
 
     #[test]
-    fn test2() {
-        let input = str_vec!["R8,U5,L5,D3", "U7,R6,D4,L4"];
-        assert_eq!(challenge(input.into_iter()), Some(30));
-    }
-
-    #[test]
-    fn test3() {
-        let input = str_vec![
-            "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
-            "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
-        ];
-        assert_eq!(challenge(input.into_iter()), Some(410));
+    fn test_bad_usage() {
+        assert!(try_parse(&["--backend"]).is_err());
+        assert!(try_parse(&["--backend", "abacus"]).is_err());
+        assert!(try_parse(&["--sort", "colour"]).is_err());
+        assert!(try_parse(&["--sort", "steps"]).is_err());
+        assert!(try_parse(&["--sort", "steps:x"]).is_err());
+        assert!(try_parse(&["--closest", "-1"]).is_err());
+        assert!(try_parse(&["--closest", "few"]).is_err());
+        assert!(try_parse(&["--metric"]).is_err());
+        assert!(try_parse(&["--metric", "taxicab"]).is_err());
+        assert!(try_parse(&["--origin", "3"]).is_err());
+        assert!(try_parse(&["--origin", "1,2,3"]).is_err());
+        assert!(try_parse(&["--origin", "1,y"]).is_err());
+        assert!(try_parse(&["--origin", "9223372036854775808,0"]).is_err());
+        assert!(try_parse(&["--svg"]).is_err());
+        assert!(try_parse(&["--verbose"]).is_err());
+        assert!(try_parse(&["a.txt", "b.txt"]).is_err());
     }
 }
@@ -0,0 +1,173 @@
+use crate::{checked_add, Coord, Intersection};
+
+type Measure<'a> = dyn Fn(&Intersection, (Coord, Coord)) -> Coord + 'a;
+
+// How far an intersection is from a reference point.
+pub enum Metric<'a> {
+    Manhattan,
+    Chebyshev,
+    // squared so that it stays a whole number
+    SquaredEuclidean,
+    // the combined steps of the wires to reach the intersection, which
+    // doesn't depend on the reference point
+    Delay,
+    Custom(Box<Measure<'a>>),
+}
+
+impl<'a> Metric<'a> {
+    // manhattan, chebyshev, euclidean or delay
+    pub fn new(input: &str) -> Result<Metric<'a>, String> {
+        match input {
+            "manhattan" => Ok(Metric::Manhattan),
+            "chebyshev" => Ok(Metric::Chebyshev),
+            "euclidean" => Ok(Metric::SquaredEuclidean),
+            "delay" => Ok(Metric::Delay),
+            _ => Err(format!("Unknown metric: {}", input)),
+        }
+    }
+
+    pub fn custom(
+        f: impl Fn(&Intersection, (Coord, Coord)) -> Coord + 'a,
+    ) -> Metric<'a> {
+        Metric::Custom(Box::new(f))
+    }
+
+    pub fn measure(&self, p: &Intersection, origin: (Coord, Coord)) -> Coord {
+        let dx = distance(p.point.0, origin.0);
+        let dy = distance(p.point.1, origin.1);
+        match self {
+            Metric::Manhattan => checked_add(dx, dy),
+            Metric::Chebyshev => dx.max(dy),
+            Metric::SquaredEuclidean => checked_add(square(dx), square(dy)),
+            Metric::Delay => p.delay(),
+            Metric::Custom(f) => f(p, origin),
+        }
+    }
+}
+
+fn distance(a: Coord, b: Coord) -> Coord {
+    match a.checked_sub(b).and_then(Coord::checked_abs) {
+        Some(d) => d,
+        None => panic!("Too far to measure: {} - {}", a, b),
+    }
+}
+
+fn square(d: Coord) -> Coord {
+    match d.checked_mul(d) {
+        Some(squared) => squared,
+        None => panic!("Too far to square: {}", d),
+    }
+}
+
+// The `k` intersections closest to `origin` by `metric` along with how far
+// each is, nearest first and breaking ties by position.
+pub fn closest<'i>(
+    intersections: &'i [Intersection],
+    metric: &Metric,
+    origin: (Coord, Coord),
+    k: usize,
+) -> Vec<(Coord, &'i Intersection)> {
+    let mut measured: Vec<(Coord, &Intersection)> = intersections
+        .iter()
+        .map(|p| (metric.measure(p, origin), p))
+        .collect();
+    measured.sort_by_key(|&(distance, p)| (distance, p.point));
+    measured.truncate(k);
+    measured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::wire::parse_wire;
+    use crate::WireSolver;
+
+    fn example() -> Vec<Intersection> {
+        let wires = vec![parse_wire("R8,U5,L5,D3"), parse_wire("U7,R6,D4,L4")];
+        Grid.intersections(&wires, 2)
+    }
+
+    fn points(
+        found: &[(Coord, &Intersection)],
+    ) -> Vec<(Coord, (Coord, Coord))> {
+        found.iter().map(|&(d, p)| (d, p.point)).collect()
+    }
+
+    #[test]
+    fn test_metrics() {
+        let found = example();
+        let all = |metric: &str| {
+            points(&closest(&found, &Metric::new(metric).unwrap(), (0, 0), 2))
+        };
+        assert_eq!(all("manhattan"), vec![(6, (3, 3)), (11, (6, 5))]);
+        assert_eq!(all("chebyshev"), vec![(3, (3, 3)), (6, (6, 5))]);
+        assert_eq!(all("euclidean"), vec![(18, (3, 3)), (61, (6, 5))]);
+        assert_eq!(all("delay"), vec![(30, (6, 5)), (40, (3, 3))]);
+    }
+
+    #[test]
+    fn test_origin() {
+        let found = example();
+        let nearest = closest(&found, &Metric::Manhattan, (6, 6), 1);
+        assert_eq!(points(&nearest), vec![(1, (6, 5))]);
+        // delay is the same from anywhere
+        let fastest = closest(&found, &Metric::Delay, (-100, 7), 1);
+        assert_eq!(points(&fastest), vec![(30, (6, 5))]);
+    }
+
+    #[test]
+    fn test_closest_k() {
+        let found = example();
+        assert!(closest(&found, &Metric::Manhattan, (0, 0), 0).is_empty());
+        assert_eq!(closest(&found, &Metric::Manhattan, (0, 0), 5).len(), 2);
+
+        // equally far from (3, 6), so in order of position
+        let tied = closest(&found, &Metric::Chebyshev, (3, 6), 2);
+        assert_eq!(points(&tied), vec![(3, (3, 3)), (3, (6, 5))]);
+        let nearest = closest(&found, &Metric::Chebyshev, (3, 6), 1);
+        assert_eq!(points(&nearest), vec![(3, (3, 3))]);
+    }
+
+    #[test]
+    fn test_custom() {
+        let found = example();
+        // fewest steps along the first wire alone
+        let first = Metric::custom(|p, _| p.steps[0].unwrap());
+        assert_eq!(
+            points(&closest(&found, &first, (0, 0), 1)),
+            vec![(15, (6, 5))]
+        );
+
+        // distance along x from a point borrowed from outside
+        let target = (5, 0);
+        let along_x = Metric::custom(|p, _| (p.point.0 - target.0).abs());
+        assert_eq!(
+            points(&closest(&found, &along_x, (0, 0), 1)),
+            vec![(1, (6, 5))]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Too far to measure")]
+    fn test_far_origin() {
+        let found = example();
+        closest(&found, &Metric::Manhattan, (Coord::MIN, 0), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too far to measure")]
+    fn test_far_point() {
+        let mut found = example();
+        found[0].point = (0, Coord::MIN);
+        closest(&found, &Metric::Chebyshev, (0, 0), 1);
+    }
+
+    #[test]
+    fn test_unknown_metric() {
+        assert_eq!(
+            Metric::new("taxicab").err(),
+            Some("Unknown metric: taxicab".to_string())
+        );
+    }
+}
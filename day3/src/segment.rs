@@ -0,0 +1,219 @@
+use crate::sweep::Sweep;
+use crate::wire::WireVec;
+use crate::{checked_add, pairwise, Coord, Crossing, Intersection, WireSolver};
+
+// Finds intersections of wires that may move diagonally by testing every
+// segment of one wire against every segment of the other, a pair of wires at
+// a time. Wires without diagonal moves are handed to the `Sweep` instead.
+pub struct Segments;
+
+impl WireSolver for Segments {
+    fn intersections(
+        &self,
+        wires: &[Vec<WireVec>],
+        k: usize,
+    ) -> Vec<Intersection> {
+        if wires.iter().flatten().any(WireVec::is_diagonal) {
+            pairwise(wires, k, wirevecs_to_segments, intersections)
+        } else {
+            Sweep.intersections(wires, k)
+        }
+    }
+}
+
+// A straight run of wire in any of the eight directions: `length` unit steps
+// of `dir` from `start`, which the wire reaches after `steps` steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub start: (Coord, Coord),
+    pub dir: (Coord, Coord),
+    pub length: Coord,
+    pub steps: Coord,
+}
+
+// Products of coordinates need twice their width.
+fn cross(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn dot(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn wide((x, y): (Coord, Coord)) -> (i128, i128) {
+    (x as i128, y as i128)
+}
+
+impl Segment {
+    // the point `t` unit steps along this segment
+    pub fn point_at(&self, t: Coord) -> (Coord, Coord) {
+        (self.start.0 + t * self.dir.0, self.start.1 + t * self.dir.1)
+    }
+
+    pub fn end(&self) -> (Coord, Coord) {
+        self.point_at(self.length)
+    }
+
+    // steps along the wire to reach a point on this segment
+    pub fn steps_to(&self, (x, y): (Coord, Coord)) -> Coord {
+        let (dx, dy) = ((x - self.start.0).abs(), (y - self.start.1).abs());
+        self.steps + dx.max(dy)
+    }
+
+    // Every lattice point on both this segment and `other`. Two diagonals can
+    // cross halfway between lattice points, where neither wire ever is, so
+    // that isn't a shared point.
+    pub fn shared_points(&self, other: &Segment) -> Vec<(Coord, Coord)> {
+        let (u, v) = (wide(self.dir), wide(other.dir));
+        let w = (
+            other.start.0 as i128 - self.start.0 as i128,
+            other.start.1 as i128 - self.start.1 as i128,
+        );
+        let (n, m) = (self.length as i128, other.length as i128);
+
+        let denominator = cross(u, v);
+        if denominator != 0 {
+            // solve start + t * u = other.start + s * v exactly
+            let (t, s) = (cross(w, v), cross(w, u));
+            if t % denominator != 0 || s % denominator != 0 {
+                return Vec::new();
+            }
+            let (t, s) = (t / denominator, s / denominator);
+            if (0..=n).contains(&t) && (0..=m).contains(&s) {
+                vec![self.point_at(t as Coord)]
+            } else {
+                Vec::new()
+            }
+        } else if cross(w, u) != 0 {
+            // parallel but on different lines
+            Vec::new()
+        } else {
+            // where the ends of `other` fall along this segment
+            let t0 = dot(w, u) / dot(u, u);
+            let t1 = t0 + m * dot(v, u) / dot(u, u);
+            let from = t0.min(t1).max(0);
+            let to = t0.max(t1).min(n);
+            (from..=to).map(|t| self.point_at(t as Coord)).collect()
+        }
+    }
+}
+
+pub fn wirevecs_to_segments(wirevecs: &[WireVec]) -> Vec<Segment> {
+    let mut start = (0, 0);
+    let mut steps = 0;
+    wirevecs
+        .iter()
+        .map(|direction| {
+            let (dx, dy, length) = direction.step();
+            let segment = Segment {
+                start,
+                dir: (dx, dy),
+                length,
+                steps,
+            };
+            start = (
+                checked_add(start.0, dx * length),
+                checked_add(start.1, dy * length),
+            );
+            steps = checked_add(steps, length);
+            segment
+        })
+        .collect()
+}
+
+// Find every lattice point shared by a segment of `wire1` and one of `wire2`.
+fn intersections(wire1: &[Segment], wire2: &[Segment]) -> Vec<Crossing> {
+    let mut points = Vec::new();
+    for (i, a) in wire1.iter().enumerate() {
+        for (j, b) in wire2.iter().enumerate() {
+            for point in a.shared_points(b) {
+                points.push((
+                    point,
+                    (a.steps_to(point), i),
+                    (b.steps_to(point), j),
+                ));
+            }
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::parse_wires;
+
+    fn parse(input: &[&str]) -> Vec<Vec<WireVec>> {
+        parse_wires(input.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_diagonal_cross() {
+        let wires = parse(&["NE4", "R4,NW4"]);
+        assert_eq!(
+            Segments.intersections(&wires, 2),
+            vec![Intersection {
+                point: (2, 2),
+                steps: vec![Some(2), Some(6)],
+                segments: vec![Some(0), Some(1)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_between_lattice_points() {
+        // the wires cross at (0.5, 0.5) but never share a point
+        let wires = parse(&["NE3", "U1,SE1"]);
+        assert!(Segments.intersections(&wires, 2).is_empty());
+        assert!(Grid.intersections(&wires, 2).is_empty());
+    }
+
+    #[test]
+    fn test_collinear_diagonals() {
+        let wires = parse(&["NE5", "U2,R2,NE2"]);
+        let found = Segments.intersections(&wires, 2);
+        let points: Vec<(Coord, Coord)> =
+            found.iter().map(|p| p.point).collect();
+        assert_eq!(points, vec![(2, 2), (3, 3), (4, 4)]);
+        assert_eq!(found[0].steps, vec![Some(2), Some(4)]);
+
+        // running back the other way
+        let found = Segments.intersections(&parse(&["NE5", "R6,U6,SW5"]), 2);
+        let points: Vec<(Coord, Coord)> =
+            found.iter().map(|p| p.point).collect();
+        assert_eq!(points, vec![(1, 1), (2, 2), (3, 3), (4, 4), (5, 5)]);
+    }
+
+    #[test]
+    fn test_beyond_i32() {
+        let wires = parse(&["NE3000000000", "R6000000000,NW3000000000"]);
+        let found = Segments.intersections(&wires, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].point, (3_000_000_000, 3_000_000_000));
+        assert_eq!(
+            found[0].steps,
+            vec![Some(3_000_000_000), Some(9_000_000_000)]
+        );
+    }
+
+    #[test]
+    fn test_matches_grid() {
+        let inputs = [
+            ["NE10,SE4", "R3,U8,SW6"],
+            ["SE7,NE7,NW7,SW7", "U3,R14,D6"],
+            ["R8,U5,L5,D3", "U7,R6,D4,L4"],
+            ["R5,NE3,L9,SE6", "NW2,R12,SW3,U9"],
+        ];
+        for input in inputs.iter() {
+            let wires = parse(input);
+            assert_eq!(
+                pairwise(&wires, 2, wirevecs_to_segments, intersections),
+                Grid.intersections(&wires, 2),
+                "{:?}",
+                input
+            );
+        }
+    }
+}
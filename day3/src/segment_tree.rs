@@ -0,0 +1,822 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::interval::{Bound, Interval, Span};
+use crate::wire::WireVec;
+use crate::{checked_add, pairwise, Coord, Crossing, Intersection, WireSolver};
+
+// Finds intersections by storing one wire's lines in segment trees and
+// querying them with the lines of the other, a pair of wires at a time.
+pub struct SegmentTreeSolver;
+
+impl WireSolver for SegmentTreeSolver {
+    fn intersections(
+        &self,
+        wires: &[Vec<WireVec>],
+        k: usize,
+    ) -> Vec<Intersection> {
+        pairwise(wires, k, wirevecs_to_lines, |a, b| {
+            let mut found = intersections(a, b);
+            found.extend(overlaps(a, b));
+            found
+        })
+    }
+}
+
+// Find every point where a line of `wire2` crosses a line of `wire1`.
+fn intersections(wire1: &[Line], wire2: &[Line]) -> Vec<Crossing> {
+    let index: HashMap<Line, usize> = wire1
+        .iter()
+        .enumerate()
+        .map(|(i, &line)| (line, i))
+        .collect();
+    let (h_lines, v_lines) = separate_and_sort_lines(wire1.to_vec());
+    let h_tree = SegmentTree::new(h_lines.clone());
+    let v_tree = SegmentTree::new(v_lines.clone());
+
+    let mut points = Vec::new();
+    for (j, line) in wire2.iter().enumerate() {
+        let crossed = match *line {
+            Line::Horizontal {
+                y_coordinate,
+                x_start,
+                x_end,
+                ..
+            } => {
+                let possible_y_intersects = v_tree.query(y_coordinate);
+                crossing(possible_y_intersects, &v_lines, x_start, x_end)
+            }
+            Line::Vertical {
+                x_coordinate,
+                y_start,
+                y_end,
+                ..
+            } => {
+                let possible_x_intersects = h_tree.query(x_coordinate);
+                crossing(possible_x_intersects, &h_lines, y_start, y_end)
+            }
+        };
+        for other in crossed {
+            let point = line.crossing_point(&other);
+            points.push((
+                point,
+                (other.steps_to(point), index[&other]),
+                (line.steps_to(point), j),
+            ));
+        }
+    }
+
+    points
+}
+
+// Find every point shared by a line of `wire1` and a line of `wire2` running
+// along the same row or column.
+pub(crate) fn overlaps(wire1: &[Line], wire2: &[Line]) -> Vec<Crossing> {
+    let rows1 = rows(wire1);
+    let mut points = Vec::new();
+    for (key, lines) in rows(wire2) {
+        let others = rows1.get(&key).map_or(&[][..], Vec::as_slice);
+        for &j in &lines {
+            for &i in others {
+                let (line, other) = (&wire2[j], &wire1[i]);
+                for point in line.shared_points(other) {
+                    points.push((
+                        point,
+                        (other.steps_to(point), i),
+                        (line.steps_to(point), j),
+                    ));
+                }
+            }
+        }
+    }
+
+    points
+}
+
+// Indices of `lines` grouped by the row or column they run along, keyed by
+// whether they are vertical and their perpendicular coordinate.
+pub(crate) fn rows(lines: &[Line]) -> HashMap<(bool, Coord), Vec<usize>> {
+    let mut rows: HashMap<(bool, Coord), Vec<usize>> = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        let vertical = matches!(line, Line::Vertical { .. });
+        let key = (vertical, line.get_perpendicular_coordinate());
+        rows.entry(key).or_default().push(i);
+    }
+    rows
+}
+
+// Narrow the lines returned by a segment tree query down to those whose
+// perpendicular coordinate falls between `from` and `to` inclusive, found by
+// binary search in `sorted`.
+fn crossing(
+    candidates: Vec<&Line>,
+    sorted: &[Line],
+    from: Coord,
+    to: Coord,
+) -> Vec<Line> {
+    let Interval { start, end, .. } = Interval::new(from, to);
+    let lo =
+        sorted.partition_point(|l| l.get_perpendicular_coordinate() < start);
+    let hi =
+        sorted.partition_point(|l| l.get_perpendicular_coordinate() <= end);
+
+    let in_range: HashSet<&Line> = sorted[lo..hi].iter().collect();
+    candidates
+        .into_iter()
+        .filter(|line| in_range.contains(line))
+        .copied()
+        .collect()
+}
+
+// `steps` is how far along the wire the line starts.
+#[derive(Copy, Clone, Debug, PartialEq, Hash, Eq)]
+pub enum Line {
+    Vertical {
+        x_coordinate: Coord,
+        y_start: Coord,
+        y_end: Coord,
+        steps: Coord,
+    },
+    Horizontal {
+        y_coordinate: Coord,
+        x_start: Coord,
+        x_end: Coord,
+        steps: Coord,
+    },
+}
+
+impl Line {
+    pub fn get_perpendicular_coordinate(&self) -> Coord {
+        match self {
+            Line::Vertical { x_coordinate, .. } => *x_coordinate,
+            Line::Horizontal { y_coordinate, .. } => *y_coordinate,
+        }
+    }
+
+    // steps along the wire to reach a point on this line
+    pub fn steps_to(&self, (x, y): (Coord, Coord)) -> Coord {
+        match *self {
+            Line::Vertical { y_start, steps, .. } => {
+                steps + (y - y_start).abs()
+            }
+            Line::Horizontal { x_start, steps, .. } => {
+                steps + (x - x_start).abs()
+            }
+        }
+    }
+
+    // where this line crosses a perpendicular one
+    pub fn crossing_point(&self, other: &Line) -> (Coord, Coord) {
+        match self {
+            Line::Vertical { x_coordinate, .. } => {
+                (*x_coordinate, other.get_perpendicular_coordinate())
+            }
+            Line::Horizontal { y_coordinate, .. } => {
+                (other.get_perpendicular_coordinate(), *y_coordinate)
+            }
+        }
+    }
+
+    // the point `t` along this line's axis
+    pub fn point_at(&self, t: Coord) -> (Coord, Coord) {
+        match *self {
+            Line::Vertical { x_coordinate, .. } => (x_coordinate, t),
+            Line::Horizontal { y_coordinate, .. } => (t, y_coordinate),
+        }
+    }
+
+    // every point on both this line and `other`, which must run along the
+    // same row or column
+    pub fn shared_points(&self, other: &Line) -> Vec<(Coord, Coord)> {
+        let shared = self.get_interval().intersection(&other.get_interval());
+        shared
+            .iter()
+            .flat_map(Interval::lattice_points)
+            .map(|t| self.point_at(t))
+            .collect()
+    }
+
+    pub fn get_interval(&self) -> Interval {
+        match self {
+            Line::Vertical { y_start, y_end, .. } => {
+                Interval::new(*y_start, *y_end)
+            }
+            Line::Horizontal { x_start, x_end, .. } => {
+                Interval::new(*x_start, *x_end)
+            }
+        }
+    }
+}
+
+// Diagonal moves have no `Line`, and are only handled by the grid and
+// `segment::Segments` backends.
+pub fn wirevecs_to_lines(wirevecs: &[WireVec]) -> Vec<Line> {
+    let (mut x, mut y) = (0, 0);
+    let mut steps = 0;
+    wirevecs
+        .iter()
+        .map(move |direction| match *direction {
+            WireVec::Up(d) => {
+                let ret = Line::Vertical {
+                    x_coordinate: x,
+                    y_start: y,
+                    y_end: checked_add(y, d),
+                    steps,
+                };
+                y = checked_add(y, d);
+                steps = checked_add(steps, d);
+                ret
+            }
+            WireVec::Down(d) => {
+                let ret = Line::Vertical {
+                    x_coordinate: x,
+                    y_start: y,
+                    y_end: checked_add(y, -d),
+                    steps,
+                };
+                y = checked_add(y, -d);
+                steps = checked_add(steps, d);
+                ret
+            }
+            WireVec::Left(d) => {
+                let ret = Line::Horizontal {
+                    y_coordinate: y,
+                    x_start: x,
+                    x_end: checked_add(x, -d),
+                    steps,
+                };
+                x = checked_add(x, -d);
+                steps = checked_add(steps, d);
+                ret
+            }
+            WireVec::Right(d) => {
+                let ret = Line::Horizontal {
+                    y_coordinate: y,
+                    x_start: x,
+                    x_end: checked_add(x, d),
+                    steps,
+                };
+                x = checked_add(x, d);
+                steps = checked_add(steps, d);
+                ret
+            }
+            diagonal => {
+                panic!("Lines must be vertical or horizontal: {:?}", diagonal)
+            }
+        })
+        .collect()
+}
+
+fn insert_sorted(e: Line, sorted: &mut Vec<Line>) {
+    let target = e.get_perpendicular_coordinate();
+    let idx = sorted
+        .binary_search_by_key(&target, |l| l.get_perpendicular_coordinate())
+        .unwrap_or_else(|x| x);
+
+    sorted.insert(idx, e)
+}
+
+// Separate vertical lines from horizontal lines.
+// Sort vertical lines by x coordinate and horizontal lines by y coordinate.
+// Return (horizontal_lines, vertical_lines)
+pub fn separate_and_sort_lines(lines: Vec<Line>) -> (Vec<Line>, Vec<Line>) {
+    let mut sorted_horiz = Vec::with_capacity(lines.len());
+    let mut sorted_vert = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        match line {
+            h @ Line::Horizontal { .. } => insert_sorted(h, &mut sorted_horiz),
+            v @ Line::Vertical { .. } => insert_sorted(v, &mut sorted_vert),
+        }
+    }
+
+    (sorted_horiz, sorted_vert)
+}
+
+impl Span for Line {
+    fn span(&self) -> Interval {
+        self.get_interval()
+    }
+}
+
+// Counts of items starting at each slot, as a Fenwick tree so that the
+// number starting in any range of slots takes O(log n) to find.
+#[derive(Debug)]
+struct Starts {
+    counts: Vec<isize>,
+}
+
+impl Starts {
+    fn new(slots: usize) -> Starts {
+        Starts {
+            counts: vec![0; slots + 1],
+        }
+    }
+
+    fn add(&mut self, slot: usize, n: isize) {
+        let mut i = slot + 1;
+        while i < self.counts.len() {
+            self.counts[i] += n;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    // the number of items starting before `slot`
+    fn before(&self, slot: usize) -> usize {
+        let (mut i, mut total) = (slot, 0);
+        while i > 0 {
+            total += self.counts[i];
+            i -= i & i.wrapping_neg();
+        }
+        total as usize
+    }
+}
+
+// A segment tree over the endpoints of the items it holds. The number line
+// is cut into slots, alternating between the gaps between endpoints and the
+// endpoints themselves:
+//
+//   slot    0      1     2      3     4
+//       (-inf, a)  a   (a, b)   b  (b, inf)
+//
+// Each node of the tree covers a range of slots and holds the items covering
+// all of it but not its parent's, so an item is stored in O(log n) nodes and
+// the nodes on the path down to any one slot hold each item covering that
+// slot exactly once.
+//
+// Inserting an item whose endpoints the tree already knows, from earlier items
+// or `with_endpoints`, takes O(log n) time. An item with a new endpoint costs
+// O(n log n) as the whole tree is rebuilt around it, so give every endpoint up
+// front when they are known. Removed items are only marked as gone until they
+// make up half of those held, when the tree is rebuilt without them.
+#[derive(Debug)]
+pub struct SegmentTree<T = Line> {
+    points: Vec<Coord>,
+    items: Vec<Option<T>>,
+    len: usize,
+    tree: Vec<Vec<usize>>,
+    starts: Starts,
+    // how many times the tree has been rebuilt
+    #[cfg(test)]
+    rebuilds: usize,
+}
+
+impl<T: Span> SegmentTree<T> {
+    pub fn new(items: Vec<T>) -> SegmentTree<T> {
+        SegmentTree::build(Vec::new(), items)
+    }
+
+    // An empty tree that takes items ending on any of `points` without being
+    // rebuilt.
+    pub fn with_endpoints(points: Vec<Coord>) -> SegmentTree<T> {
+        SegmentTree::build(points, Vec::new())
+    }
+
+    // a tree over `points` and the ends of `items`, holding `items`
+    fn build(mut points: Vec<Coord>, items: Vec<T>) -> SegmentTree<T> {
+        let items: Vec<T> = items
+            .into_iter()
+            .filter(|item| !item.span().is_empty())
+            .collect();
+        for item in &items {
+            let Interval { start, end, .. } = item.span();
+            points.push(start);
+            points.push(end);
+        }
+        points.sort_unstable();
+        points.dedup();
+
+        let slots = 2 * points.len() + 1;
+        let mut tree = SegmentTree {
+            points,
+            items: Vec::with_capacity(items.len()),
+            len: 0,
+            tree: vec![Vec::new(); 4 * slots],
+            starts: Starts::new(slots),
+            #[cfg(test)]
+            rebuilds: 0,
+        };
+        for item in items {
+            tree.insert(item);
+        }
+        tree
+    }
+
+    // rebuild over the same endpoints, plus any of `extra`, dropping the
+    // items that have been removed
+    fn rebuild(&mut self, extra: Option<T>) {
+        let points = std::mem::take(&mut self.points);
+        let mut items: Vec<T> = self.items.drain(..).flatten().collect();
+        items.extend(extra);
+        #[cfg(test)]
+        let rebuilds = self.rebuilds + 1;
+        *self = SegmentTree::build(points, items);
+        #[cfg(test)]
+        {
+            self.rebuilds = rebuilds;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn slots(&self) -> usize {
+        2 * self.points.len() + 1
+    }
+
+    // the slot holding `p`
+    fn slot(&self, p: Coord) -> usize {
+        match self.points.binary_search(&p) {
+            Ok(i) => 2 * i + 1,
+            Err(i) => 2 * i,
+        }
+    }
+
+    // the slots an interval covers, if both its ends are endpoints, leaving
+    // out the slot at an open end
+    fn slot_range(&self, interval: Interval) -> Option<(usize, usize)> {
+        let start = self.points.binary_search(&interval.start).ok()?;
+        let end = self.points.binary_search(&interval.end).ok()?;
+        let from = match interval.start_bound {
+            Bound::Closed => 2 * start + 1,
+            Bound::Open => 2 * start + 2,
+        };
+        let to = match interval.end_bound {
+            Bound::Closed => 2 * end + 1,
+            Bound::Open => 2 * end,
+        };
+        Some((from, to))
+    }
+
+    // Empty intervals cover nothing, so like `IntervalTree` the tree never
+    // keeps them.
+    pub fn insert(&mut self, item: T) {
+        if item.span().is_empty() {
+            return;
+        }
+        let (from, to) = match self.slot_range(item.span()) {
+            Some(range) => range,
+            None => return self.rebuild(Some(item)),
+        };
+
+        let id = self.items.len();
+        self.items.push(Some(item));
+        self.len += 1;
+        self.starts.add(from, 1);
+        let last = self.slots() - 1;
+        self.update(0, (0, last), (from, to), &mut |ids| ids.push(id));
+    }
+
+    // Remove an item equal to `item`, returning whether there was one.
+    pub fn remove(&mut self, item: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        if item.span().is_empty() {
+            return false;
+        }
+        let (from, to) = match self.slot_range(item.span()) {
+            Some(range) => range,
+            None => return false,
+        };
+        // the item is held exactly once on the path down to its first slot
+        let found = self
+            .path(from)
+            .into_iter()
+            .flatten()
+            .copied()
+            .find(|&id| self.items[id].as_ref() == Some(item));
+        let id = match found {
+            Some(id) => id,
+            None => return false,
+        };
+
+        self.items[id] = None;
+        self.len -= 1;
+        self.starts.add(from, -1);
+        let last = self.slots() - 1;
+        self.update(0, (0, last), (from, to), &mut |ids| {
+            ids.retain(|&other| other != id)
+        });
+        if 2 * self.len < self.items.len() {
+            self.rebuild(None);
+        }
+        true
+    }
+
+    // apply `f` to the nodes under `node` that make up the slots `from..=to`
+    fn update(
+        &mut self,
+        node: usize,
+        (lo, hi): (usize, usize),
+        (from, to): (usize, usize),
+        f: &mut dyn FnMut(&mut Vec<usize>),
+    ) {
+        if to < lo || hi < from {
+            return;
+        }
+        if from <= lo && hi <= to {
+            f(&mut self.tree[node]);
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        self.update(2 * node + 1, (lo, mid), (from, to), f);
+        self.update(2 * node + 2, (mid + 1, hi), (from, to), f);
+    }
+
+    // the items held by each node on the path from the root to `slot`
+    fn path(&self, slot: usize) -> Vec<&[usize]> {
+        let mut path = Vec::new();
+        let (mut node, mut lo, mut hi) = (0, 0, self.slots() - 1);
+        loop {
+            path.push(&self.tree[node][..]);
+            if lo == hi {
+                return path;
+            }
+            let mid = (lo + hi) / 2;
+            if slot <= mid {
+                node = 2 * node + 1;
+                hi = mid;
+            } else {
+                node = 2 * node + 2;
+                lo = mid + 1;
+            }
+        }
+    }
+
+    fn get(&self, id: usize) -> &T {
+        self.items[id].as_ref().unwrap()
+    }
+
+    // every item whose interval contains `p`
+    pub fn query(&self, p: Coord) -> Vec<&T> {
+        let path = self.path(self.slot(p));
+        path.into_iter().flatten().map(|&id| self.get(id)).collect()
+    }
+
+    // every item whose interval overlaps `[a, b]`
+    pub fn query_range(&self, a: Coord, b: Coord) -> Vec<&T> {
+        let Interval { start, end, .. } = Interval::new(a, b);
+        let (from, to) = (self.slot(start), self.slot(end));
+        let mut ids = Vec::new();
+        let mut stack = vec![(0, 0, self.slots() - 1)];
+        while let Some((node, lo, hi)) = stack.pop() {
+            if to < lo || hi < from {
+                continue;
+            }
+            ids.extend_from_slice(&self.tree[node]);
+            if lo < hi {
+                let mid = (lo + hi) / 2;
+                stack.push((2 * node + 1, lo, mid));
+                stack.push((2 * node + 2, mid + 1, hi));
+            }
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        ids.into_iter().map(|id| self.get(id)).collect()
+    }
+
+    // the number of items whose interval contains `p`
+    pub fn count(&self, p: Coord) -> usize {
+        self.path(self.slot(p)).iter().map(|ids| ids.len()).sum()
+    }
+
+    // the number of items whose interval overlaps `[a, b]`: those containing
+    // `a` and those starting after it, up to `b`
+    pub fn count_range(&self, a: Coord, b: Coord) -> usize {
+        let Interval { start, end, .. } = Interval::new(a, b);
+        let (from, to) = (self.slot(start), self.slot(end));
+        self.count(start) + self.starts.before(to + 1)
+            - self.starts.before(from + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::parse_wire;
+
+    #[test]
+    fn test_segment_tree2() {
+        let line1 = wirevecs_to_lines(&parse_wire("R8,U5,L5,D3"));
+        let (h_lines, _) = separate_and_sort_lines(line1);
+        let h_tree = SegmentTree::new(h_lines);
+
+        let result = h_tree.query(6);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_segment_tree() {
+        let line1 = Line::Horizontal {
+            x_start: 0,
+            x_end: 10,
+            y_coordinate: 0,
+            steps: 0,
+        };
+        let line2 = Line::Horizontal {
+            x_start: 5,
+            x_end: 15,
+            y_coordinate: 0,
+            steps: 0,
+        };
+
+        let segments = vec![line1, line2];
+        let tree = SegmentTree::new(segments);
+
+        let q = tree.query(-1);
+        assert!(q.is_empty());
+
+        let q = tree.query(1);
+        assert_eq!(q.len(), 1);
+        assert!(q.contains(&&line1));
+
+        let q = tree.query(6);
+        assert_eq!(q.len(), 2);
+        assert!(q.contains(&&line1));
+        assert!(q.contains(&&line2));
+
+        let q = tree.query(11);
+        assert_eq!(q.len(), 1);
+        assert!(q.contains(&&line2));
+
+        let q = tree.query(20);
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn test_segment_tree_ends() {
+        let tree = SegmentTree::new(vec![
+            Interval::new(0, 10),
+            Interval::new(5, 15),
+            Interval::new(10, 10),
+        ]);
+        assert_eq!(tree.len(), 3);
+        // ends are included, and each item is found once
+        assert_eq!(tree.query(10).len(), 3);
+        assert_eq!(tree.count(10), 3);
+        assert_eq!(tree.count(5), 2);
+        assert_eq!(tree.count(15), 1);
+        assert_eq!(tree.count(16), 0);
+        assert_eq!(tree.count(Coord::MIN), 0);
+    }
+
+    #[test]
+    fn test_segment_tree_range() {
+        let items: Vec<Interval> = [(0, 2), (4, 6), (8, 10), (1, 9)]
+            .iter()
+            .map(|&(a, b)| Interval::new(a, b))
+            .collect();
+        let tree = SegmentTree::new(items.clone());
+
+        let mut found: Vec<Interval> =
+            tree.query_range(3, 5).into_iter().copied().collect();
+        found.sort_by_key(|i| (i.start, i.end));
+        assert_eq!(found, vec![items[3], items[1]]);
+
+        // against checking every item, over ranges both ways round
+        for a in -1..=11 {
+            for b in -1..=11 {
+                let range = Interval::new(a, b);
+                let expected =
+                    items.iter().filter(|i| i.intersects(&range)).count();
+                assert_eq!(tree.query_range(a, b).len(), expected);
+                assert_eq!(tree.count_range(a, b), expected, "{} {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_segment_tree_insert_remove() {
+        let mut tree: SegmentTree<(usize, Interval)> = SegmentTree::new(vec![]);
+        assert!(tree.is_empty());
+        assert!(tree.query(0).is_empty());
+
+        // new endpoints rebuild the tree, known ones don't
+        tree.insert((0, Interval::new(0, 10)));
+        tree.insert((1, Interval::new(5, 20)));
+        tree.insert((2, Interval::new(5, 10)));
+        assert_eq!(tree.rebuilds, 2);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.count(7), 3);
+        assert_eq!(tree.count_range(11, 30), 1);
+
+        assert!(tree.remove(&(2, Interval::new(5, 10))));
+        assert!(!tree.remove(&(2, Interval::new(5, 10))));
+        assert!(!tree.remove(&(3, Interval::new(-5, 10))));
+        assert_eq!(tree.len(), 2);
+        let mut ids: Vec<usize> =
+            tree.query(7).into_iter().map(|&(id, _)| id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(tree.count_range(5, 5), 2);
+
+        // a duplicate is a separate item
+        tree.insert((0, Interval::new(0, 10)));
+        assert!(tree.remove(&(0, Interval::new(0, 10))));
+        assert_eq!(tree.count(0), 1);
+    }
+
+    #[test]
+    fn test_segment_tree_known_endpoints() {
+        let mut tree = SegmentTree::with_endpoints((0..100).collect());
+        for a in 0..50 {
+            tree.insert(Interval::new(a, 99 - a));
+        }
+        // no endpoint was new, so the tree was never rebuilt
+        assert_eq!(tree.rebuilds, 0);
+        assert_eq!(tree.count(49), 50);
+        assert_eq!(tree.count(0), 1);
+
+        // a new endpoint rebuilds it, keeping the old ones
+        tree.insert(Interval::new(-5, 0));
+        assert_eq!(tree.rebuilds, 1);
+        assert_eq!(tree.points.len(), 101);
+        assert_eq!(tree.count(0), 2);
+        tree.insert(Interval::new(-5, 99));
+        assert_eq!(tree.rebuilds, 1);
+        assert_eq!(tree.count(-5), 2);
+    }
+
+    #[test]
+    fn test_segment_tree_empty_items() {
+        let mut tree = SegmentTree::new(vec![
+            Interval::open(5, 5),
+            Interval::closed(0, 10),
+            Interval::half_open(3, 3),
+        ]);
+        assert_eq!(tree.len(), 1);
+        // their endpoints aren't taken either
+        assert_eq!(tree.points, vec![0, 10]);
+
+        tree.insert(Interval::open(5, 5));
+        tree.insert(Interval::half_open(3, 3));
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.count(5), 1);
+        assert!(!tree.remove(&Interval::open(5, 5)));
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_segment_tree_compacts() {
+        let mut tree = SegmentTree::new(vec![]);
+        for a in 0..64 {
+            tree.insert(Interval::new(a, a + 10));
+        }
+        let rebuilds = tree.rebuilds;
+        for a in 0..60 {
+            assert!(tree.remove(&Interval::new(a, a + 10)));
+            // removed items never outnumber those left
+            assert!(tree.items.len() <= 2 * tree.len());
+        }
+        assert!(tree.rebuilds > rebuilds);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.count(65), 4);
+        assert_eq!(tree.count_range(0, 59), 0);
+        // every endpoint is still known after compacting
+        let rebuilds = tree.rebuilds;
+        tree.insert(Interval::new(0, 10));
+        assert_eq!(tree.rebuilds, rebuilds);
+        assert_eq!(tree.count(5), 1);
+    }
+
+    #[test]
+    fn test_segment_tree_open_ends() {
+        let tree = SegmentTree::new(vec![
+            Interval::half_open(0, 5),
+            Interval::half_open(5, 10),
+            Interval::open(10, 12),
+        ]);
+        assert_eq!(tree.query(5), vec![&Interval::half_open(5, 10)]);
+        assert_eq!(tree.count(0), 1);
+        assert_eq!(tree.count(10), 0);
+        assert_eq!(tree.count(11), 1);
+        assert_eq!(tree.count_range(4, 5), 2);
+        assert_eq!(tree.count_range(10, 10), 0);
+        assert_eq!(tree.query_range(10, 20).len(), 1);
+    }
+
+    #[test]
+    fn test_steps() {
+        let lines = wirevecs_to_lines(&parse_wire("R8,U5"));
+        assert_eq!(lines[1].steps_to((8, 3)), 11);
+        assert_eq!(lines[0].steps_to((2, 0)), 2);
+
+        let wire1 = wirevecs_to_lines(&parse_wire("U5,R5,D10,L10,U5,R12"));
+        let wire2 = wirevecs_to_lines(&parse_wire("R2,U8"));
+        let mut crossings = intersections(&wire1, &wire2);
+        crossings.sort();
+        assert_eq!(
+            crossings,
+            vec![
+                ((0, 0), (0, 0), (0, 0)),
+                ((2, 0), (42, 5), (2, 1)),
+                ((2, 5), (7, 1), (7, 1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use crate::interval::{Interval, Span};
+use crate::segment_tree::SegmentTree;
+use crate::{checked_add, pairwise, Coord, Crossing, Intersection};
+
+pub type Point3 = (Coord, Coord, Coord);
+
+// A move through three dimensions: the four moves of `WireVec` in the plane
+// of the board, and `In` or `Out` along z, with in towards positive z.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WireVec3 {
+    Up(Coord),
+    Down(Coord),
+    Left(Coord),
+    Right(Coord),
+    In(Coord),
+    Out(Coord),
+}
+
+impl WireVec3 {
+    pub fn new(input: &str) -> WireVec3 {
+        match input.chars().next() {
+            Some('U') => WireVec3::Up(input[1..].parse().unwrap()),
+            Some('D') => WireVec3::Down(input[1..].parse().unwrap()),
+            Some('L') => WireVec3::Left(input[1..].parse().unwrap()),
+            Some('R') => WireVec3::Right(input[1..].parse().unwrap()),
+            Some('I') => WireVec3::In(input[1..].parse().unwrap()),
+            Some('O') => WireVec3::Out(input[1..].parse().unwrap()),
+            dir => panic!("Unknown direction: {:?}", dir),
+        }
+    }
+
+    // the axis to move along, 0 to 2 for x to z, and how far to move
+    // along it
+    pub fn step(&self) -> (usize, Coord) {
+        match *self {
+            WireVec3::Up(d) => (1, d),
+            WireVec3::Down(d) => (1, -d),
+            WireVec3::Left(d) => (0, -d),
+            WireVec3::Right(d) => (0, d),
+            WireVec3::In(d) => (2, d),
+            WireVec3::Out(d) => (2, -d),
+        }
+    }
+}
+
+pub fn parse_wire3(line: &str) -> Vec<WireVec3> {
+    line.split(',').map(WireVec3::new).collect()
+}
+
+pub fn parse_wires3(input: impl Iterator<Item = String>) -> Vec<Vec<WireVec3>> {
+    input
+        .filter(|line| !line.is_empty())
+        .map(|line| parse_wire3(&line))
+        .collect()
+}
+
+// A run of wire along one axis from `start` to `end` on that axis, which the
+// wire reaches after `steps` steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line3 {
+    pub axis: usize,
+    pub start: [Coord; 3],
+    pub end: Coord,
+    pub steps: Coord,
+}
+
+impl Line3 {
+    // where the line runs along its axis
+    pub fn get_interval(&self) -> Interval {
+        Interval::new(self.start[self.axis], self.end)
+    }
+
+    // the point `t` along this line's axis
+    pub fn point_at(&self, t: Coord) -> [Coord; 3] {
+        let mut point = self.start;
+        point[self.axis] = t;
+        point
+    }
+
+    pub fn contains(&self, point: [Coord; 3]) -> bool {
+        (0..3).all(|axis| {
+            if axis == self.axis {
+                self.get_interval().contains_point(point[axis])
+            } else {
+                self.start[axis] == point[axis]
+            }
+        })
+    }
+
+    // steps along the wire to reach a point on this line
+    pub fn steps_to(&self, point: [Coord; 3]) -> Coord {
+        self.steps + (point[self.axis] - self.start[self.axis]).abs()
+    }
+
+    // the other two coordinates, which stay the same along the line
+    fn row(&self) -> (usize, [Coord; 3]) {
+        (self.axis, self.point_at(0))
+    }
+}
+
+impl Span for Line3 {
+    fn span(&self) -> Interval {
+        self.get_interval()
+    }
+}
+
+pub fn wirevecs3_to_lines(wirevecs: &[WireVec3]) -> Vec<Line3> {
+    let mut position = [0; 3];
+    let mut steps = 0;
+    wirevecs
+        .iter()
+        .map(|direction| {
+            let (axis, d) = direction.step();
+            let line = Line3 {
+                axis,
+                start: position,
+                end: checked_add(position[axis], d),
+                steps,
+            };
+            position[axis] = line.end;
+            steps = checked_add(steps, d.abs());
+            line
+        })
+        .collect()
+}
+
+impl Intersection<Point3> {
+    // Manhattan distance from the origin
+    pub fn distance(&self) -> Coord {
+        let (x, y, z) = self.point;
+        checked_add(checked_add(x.abs(), y.abs()), z.abs())
+    }
+}
+
+// Every point other than the origin crossed by at least `k` of `wires`,
+// ordered by position. `k` must be at least 1.
+pub fn intersections(
+    wires: &[Vec<WireVec3>],
+    k: usize,
+) -> Vec<Intersection<Point3>> {
+    pairwise(wires, k, wirevecs3_to_lines, crossings)
+}
+
+// Find every point shared by a line of `wire1` and a line of `wire2`. The
+// lines of `wire1` running along each axis go in a segment tree over that
+// axis, which each line of `wire2` queries with its own coordinate on it.
+fn crossings(wire1: &[Line3], wire2: &[Line3]) -> Vec<Crossing<Point3>> {
+    let trees: Vec<SegmentTree<(usize, Line3)>> = (0..3)
+        .map(|axis| {
+            let along = wire1.iter().copied().enumerate();
+            SegmentTree::new(along.filter(|(_, l)| l.axis == axis).collect())
+        })
+        .collect();
+    let mut rows: HashMap<(usize, [Coord; 3]), Vec<usize>> = HashMap::new();
+    for (i, line) in wire1.iter().enumerate() {
+        rows.entry(line.row()).or_default().push(i);
+    }
+
+    let mut points = Vec::new();
+    let mut meet = |point, (i, a): (usize, &Line3), (j, b): (usize, &Line3)| {
+        let [x, y, z] = point;
+        points.push((
+            (x, y, z),
+            (a.steps_to(point), i),
+            (b.steps_to(point), j),
+        ));
+    };
+    for (j, line) in wire2.iter().enumerate() {
+        for (axis, tree) in trees.iter().enumerate() {
+            if axis == line.axis {
+                continue;
+            }
+            for &(i, other) in tree.query(line.start[axis]) {
+                let point = line.point_at(other.start[line.axis]);
+                if other.contains(point) && line.contains(point) {
+                    meet(point, (i, &other), (j, line));
+                }
+            }
+        }
+        for &i in rows.get(&line.row()).into_iter().flatten() {
+            let other = &wire1[i];
+            let shared =
+                other.get_interval().intersection(&line.get_interval());
+            for t in shared.iter().flat_map(Interval::lattice_points) {
+                meet(line.point_at(t), (i, other), (j, line));
+            }
+        }
+    }
+
+    points
+}
+
+// Returns the distance to the closest intersection and the fewest combined
+// steps to reach one.
+pub fn challenge(
+    input: impl Iterator<Item = String>,
+) -> Option<(Coord, Coord)> {
+    let wires = parse_wires3(input);
+    let found = intersections(&wires, 2);
+    let closest = found.iter().map(|p| p.distance()).min()?;
+    let fastest = found.iter().map(|p| p.delay()).min()?;
+    Some((closest, fastest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[&str]) -> Vec<Vec<WireVec3>> {
+        parse_wires3(input.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_flat() {
+        // wires that never leave the board cross as they do in two dimensions
+        let input = [
+            "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+            "U62,R66,U55,R34,D71,R55,D58,R83",
+        ];
+        let input = input.iter().map(|s| s.to_string());
+        assert_eq!(challenge(input), Some((159, 610)));
+    }
+
+    #[test]
+    fn test_crossing_in_depth() {
+        let found = intersections(&parse(&["R2,O4", "O2,R4"]), 2);
+        assert_eq!(
+            found,
+            vec![Intersection {
+                point: (2, 0, -2),
+                steps: vec![Some(4), Some(4)],
+                segments: vec![Some(1), Some(1)],
+            }]
+        );
+        assert_eq!(found[0].distance(), 4);
+    }
+
+    #[test]
+    fn test_passing_over() {
+        // the second wire passes under (2, 3, 2) at (2, 3, 0)
+        assert!(intersections(&parse(&["U3,I2,R4", "R2,U3"]), 2).is_empty());
+    }
+
+    #[test]
+    fn test_collinear() {
+        let found = intersections(&parse(&["I6", "R1,L1,I3"]), 2);
+        let points: Vec<Point3> = found.iter().map(|p| p.point).collect();
+        assert_eq!(points, vec![(0, 0, 1), (0, 0, 2), (0, 0, 3)]);
+        assert_eq!(found[0].steps, vec![Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn test_beyond_i32() {
+        let found =
+            intersections(&parse(&["I3000000000,R1", "R1,I3000000000"]), 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].point, (1, 0, 3_000_000_000));
+        assert_eq!(found[0].distance(), 3_000_000_001);
+        assert_eq!(found[0].delay(), 6_000_000_002);
+    }
+
+    #[test]
+    fn test_many_wires() {
+        // three wires through (1, 1, 1) from different directions
+        let wires = parse(&["R1,U1,I1", "I1,R1,U1", "U1,I1,R1"]);
+        let found = intersections(&wires, 3);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].point, (1, 1, 1));
+        assert_eq!(found[0].delay(), 9);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Write;
+
+use crate::metric::{closest, Metric};
+use crate::segment::{wirevecs_to_segments, Segment};
+use crate::wire::WireVec;
+use crate::{Coord, Intersection};
+
+// Wires take these colours in turn.
+const COLOURS: [&str; 6] = [
+    "#d62728", "#1f77b4", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf",
+];
+
+// The smallest (min_x, max_x, min_y, max_y) covering the origin and every
+// segment of every wire.
+pub fn find_bounds(wires: &[Vec<Segment>]) -> (Coord, Coord, Coord, Coord) {
+    let (mut min_x, mut max_x) = (0, 0);
+    let (mut min_y, mut max_y) = (0, 0);
+    for (x, y) in wires.iter().flatten().map(Segment::end) {
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    (min_x, max_x, min_y, max_y)
+}
+
+// how far `max` is past `min`
+fn width(min: Coord, max: Coord) -> Coord {
+    match max.checked_sub(min) {
+        Some(width) => width,
+        None => panic!("Too wide to draw: {} to {}", min, max),
+    }
+}
+
+// Draw `wires` and their `intersections` as an SVG image, picking out the
+// intersection nearest `origin` by `metric`. The view fits the wires with a
+// small margin, and y runs up the page as it does on the board.
+pub fn render(
+    wires: &[Vec<WireVec>],
+    intersections: &[Intersection],
+    metric: &Metric,
+    origin: (Coord, Coord),
+) -> String {
+    let segments: Vec<Vec<Segment>> =
+        wires.iter().map(|w| wirevecs_to_segments(w)).collect();
+    let (min_x, max_x, min_y, max_y) = find_bounds(&segments);
+    // as the bounds cover the origin, min_y can't be Coord::MIN from here on
+    // and every y on a wire can be flipped
+    let (width, height) = (width(min_x, max_x), width(min_y, max_y));
+    let size = width.max(height).max(1) as f64;
+    let margin = size / 20.0;
+    let radius = size / 150.0;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        min_x as f64 - margin,
+        -max_y as f64 - margin,
+        width as f64 + 2.0 * margin,
+        height as f64 + 2.0 * margin,
+    )
+    .unwrap();
+    for (i, wire) in segments.iter().enumerate() {
+        let mut points = String::from("0,0");
+        for (x, y) in wire.iter().map(Segment::end) {
+            write!(points, " {},{}", x, -y).unwrap();
+        }
+        writeln!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" \
+             stroke-width=\"2\" vector-effect=\"non-scaling-stroke\"/>",
+            points,
+            COLOURS[i % COLOURS.len()],
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        "<circle cx=\"0\" cy=\"0\" r=\"{}\" fill=\"black\"><title>origin\
+         </title></circle>",
+        2.0 * radius,
+    )
+    .unwrap();
+
+    let winner = closest(intersections, metric, origin, 1)
+        .first()
+        .map(|&(_, p)| p.point);
+    for p in intersections {
+        let (x, y) = p.point;
+        let steps: Vec<String> = p
+            .steps
+            .iter()
+            .map(|s| s.map_or("-".to_string(), |s| s.to_string()))
+            .collect();
+        let (r, fill) = if Some(p.point) == winner {
+            (2.0 * radius, "gold")
+        } else {
+            (radius, "black")
+        };
+        writeln!(
+            svg,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\">\
+             <title>({}, {}) steps {}</title></circle>",
+            x,
+            -y,
+            r,
+            fill,
+            x,
+            y,
+            steps.join(", "),
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_wires;
+    use crate::segment::Segments;
+    use crate::sweep::Sweep;
+    use crate::WireSolver;
+
+    fn parse(input: &[&str]) -> Vec<Vec<WireVec>> {
+        parse_wires(input.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_find_bounds() {
+        let wires = parse(&["R8,U5,L5,D3", "U7,R6,D4,L4"]);
+        let segments: Vec<Vec<Segment>> =
+            wires.iter().map(|w| wirevecs_to_segments(w)).collect();
+        assert_eq!(find_bounds(&segments), (0, 8, 0, 7));
+
+        let segments = vec![wirevecs_to_segments(&parse(&["L3,D2,R1"])[0])];
+        assert_eq!(find_bounds(&segments), (-3, 0, -2, 0));
+        assert_eq!(find_bounds(&[]), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_render() {
+        let wires = parse(&["R8,U5,L5,D3", "U7,R6,D4,L4"]);
+        let found = Sweep.intersections(&wires, 2);
+        let svg = render(&wires, &found, &Metric::Manhattan, (0, 0));
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // 8 by 7 with a margin of 0.4 on each side, flipped upright
+        assert!(svg.contains("viewBox=\"-0.4 -7.4 8.8 7.8\""));
+        assert!(svg.contains("points=\"0,0 8,0 8,-5 3,-5 3,-2\""));
+        assert!(svg.contains("points=\"0,0 0,-7 6,-7 6,-3 2,-3\""));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("<title>origin</title>"));
+        assert!(svg.contains("<title>(3, 3) steps 20, 20</title>"));
+        assert!(svg.contains("<title>(6, 5) steps 15, 15</title>"));
+        assert_eq!(svg.matches("fill=\"gold\"").count(), 1);
+        assert!(svg.contains(
+            "cx=\"3\" cy=\"-3\" r=\"0.10666666666666667\" fill=\"gold\""
+        ));
+
+        // the fastest intersection wins by delay instead
+        let svg = render(&wires, &found, &Metric::Delay, (0, 0));
+        assert!(svg.contains(
+            "cx=\"6\" cy=\"-5\" r=\"0.10666666666666667\" fill=\"gold\""
+        ));
+    }
+
+    #[test]
+    fn test_render_diagonal() {
+        let wires = parse(&["NE4", "R4,NW4"]);
+        let found = Segments.intersections(&wires, 2);
+        let svg = render(&wires, &found, &Metric::Manhattan, (0, 0));
+        assert!(svg.contains("viewBox=\"-0.2 -4.2 4.4 4.4\""));
+        assert!(svg.contains("points=\"0,0 4,-4\""));
+        assert!(svg.contains("points=\"0,0 4,0 0,-4\""));
+        assert!(svg.contains("<title>(2, 2) steps 2, 6</title>"));
+        assert!(svg.contains("cx=\"2\" cy=\"-2\""));
+    }
+
+    #[test]
+    #[should_panic(expected = "Too wide to draw")]
+    fn test_too_wide() {
+        let wires = parse(&["L5000000000000000000", "R5000000000000000000"]);
+        render(&wires, &[], &Metric::Manhattan, (0, 0));
+    }
+
+    #[test]
+    fn test_missing_steps() {
+        // the third wire never reaches (2, 2)
+        let wires = parse(&["R2,U4", "U2,R4", "L1"]);
+        let found = Sweep.intersections(&wires, 2);
+        let svg = render(&wires, &found, &Metric::Manhattan, (0, 0));
+        assert!(svg.contains("<title>(2, 2) steps 4, 4, -</title>"));
+        assert_eq!(svg.matches("<polyline").count(), 3);
+    }
+
+    #[test]
+    fn test_no_intersections() {
+        let wires = parse(&["R2", "U2"]);
+        let svg = render(&wires, &[], &Metric::Manhattan, (0, 0));
+        assert!(!svg.contains("gold"));
+        assert_eq!(svg.matches("<circle").count(), 1);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeSet;
+
+use crate::segment_tree::{overlaps, wirevecs_to_lines, Line};
+use crate::wire::WireVec;
+use crate::{pairwise, Coord, Crossing, Intersection, WireSolver};
+
+// Finds intersections by sweeping a vertical line across the plane, keeping
+// the horizontal lines it currently meets ordered by y, a pair of wires at a
+// time. Each pair takes O((n + k) log n) for n lines and k crossings, plus
+// O(m²) for the m lines that run along the same row or column as a line of
+// the other wire, whose overlaps are found by comparing them pairwise.
+pub struct Sweep;
+
+impl WireSolver for Sweep {
+    fn intersections(
+        &self,
+        wires: &[Vec<WireVec>],
+        k: usize,
+    ) -> Vec<Intersection> {
+        pairwise(wires, k, wirevecs_to_lines, |a, b| {
+            let mut found = intersections(a, b);
+            found.extend(overlaps(a, b));
+            found
+        })
+    }
+}
+
+// Events at the same x are handled in this order, so that lines which only
+// touch at their ends still meet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Event {
+    Enter(usize),
+    Cross(usize),
+    Leave(usize),
+}
+
+// Find every point where a line of `wire2` meets a line of `wire1`.
+fn intersections(wire1: &[Line], wire2: &[Line]) -> Vec<Crossing> {
+    let crossing = |h: &Line, v: &Line| {
+        let point = v.crossing_point(h);
+        (point, h.steps_to(point), v.steps_to(point))
+    };
+
+    let mut points = Vec::new();
+    for (i, j) in sweep(wire1, wire2) {
+        let (point, a, b) = crossing(&wire1[i], &wire2[j]);
+        points.push((point, (a, i), (b, j)));
+    }
+    for (j, i) in sweep(wire2, wire1) {
+        let (point, b, a) = crossing(&wire2[j], &wire1[i]);
+        points.push((point, (a, i), (b, j)));
+    }
+
+    points
+}
+
+// Pairs of indices of a horizontal line in `h_wire` and a vertical line in
+// `v_wire` that meet, ends included. Lines running the same way are ignored.
+pub(crate) fn sweep(h_wire: &[Line], v_wire: &[Line]) -> Vec<(usize, usize)> {
+    let mut events = Vec::with_capacity(2 * h_wire.len() + v_wire.len());
+    for (i, line) in h_wire.iter().enumerate() {
+        if let Line::Horizontal { .. } = line {
+            let interval = line.get_interval();
+            events.push((interval.start, Event::Enter(i)));
+            events.push((interval.end, Event::Leave(i)));
+        }
+    }
+    for (j, line) in v_wire.iter().enumerate() {
+        if let Line::Vertical { x_coordinate, .. } = *line {
+            events.push((x_coordinate, Event::Cross(j)));
+        }
+    }
+    events.sort_unstable();
+
+    // horizontal lines under the sweep, by y and then index
+    let mut active: BTreeSet<(Coord, usize)> = BTreeSet::new();
+    let mut pairs = Vec::new();
+    for (_, event) in events {
+        match event {
+            Event::Enter(i) => {
+                active.insert((h_wire[i].get_perpendicular_coordinate(), i));
+            }
+            Event::Leave(i) => {
+                active.remove(&(h_wire[i].get_perpendicular_coordinate(), i));
+            }
+            Event::Cross(j) => {
+                let interval = v_wire[j].get_interval();
+                let range = (interval.start, 0)..=(interval.end, usize::MAX);
+                pairs.extend(active.range(range).map(|&(_, i)| (i, j)));
+            }
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wire::parse_wire;
+
+    #[test]
+    fn test_sweep() {
+        let h_wire = wirevecs_to_lines(&parse_wire("R8,U5,L5,D3"));
+        let v_wire = wirevecs_to_lines(&parse_wire("U7,R6,D4,L4"));
+        let mut pairs = sweep(&h_wire, &v_wire);
+        pairs.sort();
+        // (6, 5) and, at the origin, the start of both wires
+        assert_eq!(pairs, vec![(0, 0), (2, 2)]);
+    }
+
+    #[test]
+    fn test_steps() {
+        let wire1 = wirevecs_to_lines(&parse_wire("U5,R5,D10,L10,U5,R12"));
+        let wire2 = wirevecs_to_lines(&parse_wire("R2,U8"));
+        let mut crossings = intersections(&wire1, &wire2);
+        crossings.sort();
+        assert_eq!(
+            crossings,
+            vec![
+                ((0, 0), (0, 0), (0, 0)),
+                ((2, 0), (42, 5), (2, 1)),
+                ((2, 5), (7, 1), (7, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_touching() {
+        // the wires only meet at the far corner, where both of them turn
+        let wires =
+            vec![parse_wire("R20000,U20000"), parse_wire("U20000,R20000")];
+        let found = Sweep.intersections(&wires, 2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].point, (20000, 20000));
+        assert_eq!(found[0].delay(), 80000);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{checked_add, Coord};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WireVec {
+    Up(Coord),
+    Down(Coord),
+    Left(Coord),
+    Right(Coord),
+    NorthEast(Coord),
+    NorthWest(Coord),
+    SouthEast(Coord),
+    SouthWest(Coord),
+}
+
+impl WireVec {
+    // a direction, U, D, L, R or diagonally NE, NW, SE, SW, then a length
+    pub fn new(input: &str) -> WireVec {
+        let split = input
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(input.len());
+        let (dir, d) = input.split_at(split);
+        let d = d.parse().unwrap();
+        match dir {
+            "U" => WireVec::Up(d),
+            "D" => WireVec::Down(d),
+            "L" => WireVec::Left(d),
+            "R" => WireVec::Right(d),
+            "NE" => WireVec::NorthEast(d),
+            "NW" => WireVec::NorthWest(d),
+            "SE" => WireVec::SouthEast(d),
+            "SW" => WireVec::SouthWest(d),
+            dir => panic!("Unknown direction: {:?}", dir),
+        }
+    }
+
+    // unit step in x and y, and the number of steps to take
+    pub fn step(&self) -> (Coord, Coord, Coord) {
+        match *self {
+            WireVec::Up(d) => (0, 1, d),
+            WireVec::Down(d) => (0, -1, d),
+            WireVec::Left(d) => (-1, 0, d),
+            WireVec::Right(d) => (1, 0, d),
+            WireVec::NorthEast(d) => (1, 1, d),
+            WireVec::NorthWest(d) => (-1, 1, d),
+            WireVec::SouthEast(d) => (1, -1, d),
+            WireVec::SouthWest(d) => (-1, -1, d),
+        }
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        let (dx, dy, _) = self.step();
+        dx != 0 && dy != 0
+    }
+
+    // the move `d` unit steps of (dx, dy), the inverse of `step`
+    fn from_step(dx: Coord, dy: Coord, d: Coord) -> WireVec {
+        match (dx, dy) {
+            (0, 1) => WireVec::Up(d),
+            (0, -1) => WireVec::Down(d),
+            (-1, 0) => WireVec::Left(d),
+            (1, 0) => WireVec::Right(d),
+            (1, 1) => WireVec::NorthEast(d),
+            (-1, 1) => WireVec::NorthWest(d),
+            (1, -1) => WireVec::SouthEast(d),
+            (-1, -1) => WireVec::SouthWest(d),
+            _ => panic!("Not a unit step: ({}, {})", dx, dy),
+        }
+    }
+
+    // the same move turned a quarter turn anticlockwise
+    pub fn turn_left(self) -> WireVec {
+        let (dx, dy, d) = self.step();
+        WireVec::from_step(-dy, dx, d)
+    }
+
+    // the same move turned a quarter turn clockwise
+    pub fn turn_right(self) -> WireVec {
+        let (dx, dy, d) = self.step();
+        WireVec::from_step(dy, -dx, d)
+    }
+
+    pub fn reverse(self) -> WireVec {
+        let (dx, dy, d) = self.step();
+        WireVec::from_step(-dx, -dy, d)
+    }
+
+    // where this move ends when it starts from `(x, y)`
+    pub fn advance(self, (x, y): (Coord, Coord)) -> (Coord, Coord) {
+        let (dx, dy, d) = self.step();
+        (checked_add(x, dx * d), checked_add(y, dy * d))
+    }
+}
+
+pub fn parse_wire(line: &str) -> Vec<WireVec> {
+    line.split(',').map(WireVec::new).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_wire("U7,NE5,L12,SW3"),
+            vec![
+                WireVec::Up(7),
+                WireVec::NorthEast(5),
+                WireVec::Left(12),
+                WireVec::SouthWest(3),
+            ]
+        );
+        assert_eq!(WireVec::new("NW2").step(), (-1, 1, 2));
+        assert!(WireVec::new("SE1").is_diagonal());
+        assert!(!WireVec::new("D1").is_diagonal());
+    }
+
+    #[test]
+    fn test_turns() {
+        let mut heading = WireVec::Up(1);
+        for _ in 0..4 {
+            heading = heading.turn_right();
+        }
+        assert_eq!(heading, WireVec::Up(1));
+        assert_eq!(WireVec::Up(2).turn_left(), WireVec::Left(2));
+        assert_eq!(WireVec::NorthEast(1).turn_left(), WireVec::NorthWest(1));
+        assert_eq!(WireVec::SouthEast(1).turn_right(), WireVec::SouthWest(1));
+        assert_eq!(WireVec::Left(4).reverse(), WireVec::Right(4));
+        assert_eq!(WireVec::Down(3).advance((0, 0)), (0, -3));
+        assert_eq!(WireVec::SouthWest(2).advance((5, 5)), (3, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown direction")]
+    fn test_unknown_direction() {
+        WireVec::new("N5");
+    }
+}